@@ -1,11 +1,20 @@
+use crate::model::users::users_models::UserRole;
+
 #[derive(Clone, Debug)]
 pub struct AuthTokenContext {
     user_id: u32,
+    role: UserRole,
+    session_id: Option<u32>,
 }
 
 impl AuthTokenContext {
-    pub fn new(user_id: u32) -> Self {
-        Self { user_id }
+    pub fn new(user_id: u32, role: UserRole) -> Self {
+        Self { user_id, role, session_id: None }
+    }
+
+    /// Context of a request authenticated by a token of the session.
+    pub fn with_session(self, session_id: u32) -> Self {
+        Self { session_id: Some(session_id), ..self }
     }
 }
 
@@ -13,4 +22,12 @@ impl AuthTokenContext {
     pub fn user_id(&self) -> u32 {
         self.user_id
     }
-}
\ No newline at end of file
+
+    pub fn is_admin(&self) -> bool {
+        self.role == UserRole::Admin
+    }
+
+    pub fn session_id(&self) -> Option<u32> {
+        self.session_id
+    }
+}
@@ -1,22 +1,29 @@
 use std::fmt::{Display, Formatter};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
+use serde::Serialize;
 use strum_macros::AsRefStr;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+pub type StorageResult<T> = core::result::Result<T, StorageError>;
+
 #[derive(Debug, Clone)]
 pub enum Error {
     User(UserError),
     Notes(NoteError),
+    Notebooks(NotebookError),
     Sessions(SessionError),
+    Request(RequestError),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
 pub enum UserError {
     //Registration
     RegisterFail,
     RegisterFailNicknameCaptured,
+    InvalidFields(Vec<FieldError>),
 
     //Login
     LoginFail,
@@ -24,6 +31,7 @@ pub enum UserError {
 
     //Authentication
     AuthFail,
+    AdminRightsRequired,
 
     //Editing
     EditFail,
@@ -35,19 +43,50 @@ pub enum UserError {
 
     //General
     UserDoesNotExists,
+    WrongPassword,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
 pub enum NoteError {
     //Creation
     CreateFail,
 
+    //Validation
+    InvalidFields(Vec<FieldError>),
+
     //Receiving
     ReceiveFail,
+    ReaderCanNotReadNote,
+
+    //Search
+    SearchFail,
+
+    //Revisions
+    RevisionDoesNotExists,
+
+    //Tags
+    TagDoesNotExists,
+    TagAlreadyExists,
+
+    //Sharing
+    ShareFail,
+    SharerCanNotShareNote,
+    ShareDoesNotExists,
+
+    //Public links
+    LinkFail,
+    LinkDoesNotExists,
+    LinkExpired,
+    LinkPasswordRequired,
+    LinkPasswordWrong,
+    LinkPasswordAttemptsExceeded,
 
     //Editing
     EditFail,
     EditorCanNotEditNote,
+    EditorCanNotMoveNote,
+    VersionMismatch(u64),
 
     //Deletion
     DeleteFail,
@@ -57,10 +96,37 @@ pub enum NoteError {
     NoteDoesNotExists,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum NotebookError {
+    //Creation
+    CreateFail,
+
+    //Validation
+    InvalidFields(Vec<FieldError>),
+
+    //Receiving
+    ReceiveFail,
+
+    //Editing
+    EditFail,
+    MoveIntoOwnSubtree,
+
+    //Deletion
+    DeleteFail,
+
+    //General
+    NotebookDoesNotExists,
+}
+
+#[derive(Debug, Clone, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
 pub enum SessionError {
     //Creation
     CreateFail,
+
+    //Receiving
+    ReceiveFail,
     
     //Deletion
     DeleteFail,
@@ -69,13 +135,57 @@ pub enum SessionError {
     ValidityCheckFail,
     SessionInvalid,
 
+    //Refreshing
+    RefreshFail,
+    RefreshTokenInvalid,
+    RefreshTokenReused,
+
     //General
     SessionDoesNotExists,
 }
 
+#[derive(Debug, Clone, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum RequestError {
+    //Body
+    MalformedBody,
+    UnsupportedMediaType,
+    BodyTooLarge,
+    InvalidFields(Vec<FieldError>),
+
+    //Path and query
+    InvalidParameters(Vec<FieldError>),
+
+    //Routing
+    RouteDoesNotExists,
+    MethodNotAllowed,
+}
+
+/// Validation failure of a single request field.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub enum StorageError {
+    //Backend is not able to serve the request
+    Unavailable,
+
+    //Unique constraint violation
+    Conflict,
+}
+
+/// Placeholder response carrying the error in its extensions;
+/// `web::response_mapper` turns it into the client status and body.
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        let mut response = StatusCode::INTERNAL_SERVER_ERROR.into_response();
+
+        response.extensions_mut().insert(self);
+
+        response
     }
 }
 
@@ -90,19 +200,58 @@ impl Error {
         match self {
             Error::User(error) => error,
             Error::Notes(error) => error,
+            Error::Notebooks(error) => error,
             Error::Sessions(error) => error,
+            Error::Request(error) => error,
+        }
+    }
+
+    /// Stable machine readable code, e.g. `user.login_fail_invalid_params`.
+    pub fn code(&self) -> String {
+        match self {
+            Error::User(error) => format!("user.{}", error.as_ref()),
+            Error::Notes(error) => format!("note.{}", error.as_ref()),
+            Error::Notebooks(error) => format!("notebook.{}", error.as_ref()),
+            Error::Sessions(error) => format!("session.{}", error.as_ref()),
+            Error::Request(error) => format!("request.{}", error.as_ref()),
+        }
+    }
+
+    pub fn field_errors(&self) -> Option<&[FieldError]> {
+        match self {
+            Error::User(UserError::InvalidFields(field_errors))
+            | Error::Notes(NoteError::InvalidFields(field_errors))
+            | Error::Notebooks(NotebookError::InvalidFields(field_errors))
+            | Error::Request(RequestError::InvalidFields(field_errors))
+            | Error::Request(RequestError::InvalidParameters(field_errors)) =>
+                Some(field_errors),
+            _ => None,
+        }
+    }
+
+    /// Version a stale note edit should be merged with.
+    pub fn current_version(&self) -> Option<u64> {
+        match self {
+            Error::Notes(NoteError::VersionMismatch(version)) => Some(*version),
+            _ => None,
         }
     }
 }
 
 pub trait ToClientStatusAndError {
     fn client_status_and_error(&self) -> (StatusCode, ClientError);
+
+    fn client_detail(&self) -> &'static str;
 }
 
 impl ToClientStatusAndError for Error {
     fn client_status_and_error(&self) -> (StatusCode, ClientError) {
         self.unwrap().client_status_and_error()
     }
+
+    fn client_detail(&self) -> &'static str {
+        self.unwrap().client_detail()
+    }
 }
 
 impl ToClientStatusAndError for UserError {
@@ -119,6 +268,10 @@ impl ToClientStatusAndError for UserError {
                 StatusCode::CONFLICT,
                 ClientError::REGISTER_FAIL
             ),
+            UserError::InvalidFields(_) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ClientError::INVALID_PARAMETERS
+            ),
             UserError::LoginFailInvalidParams => (
                 StatusCode::FORBIDDEN,
                 ClientError::LOGIN_FAIL
@@ -139,6 +292,31 @@ impl ToClientStatusAndError for UserError {
                 StatusCode::FORBIDDEN,
                 ClientError::NO_AUTHENTICATION
             ),
+            UserError::AdminRightsRequired
+            | UserError::WrongPassword => (
+                StatusCode::FORBIDDEN,
+                ClientError::NO_RIGHTS
+            ),
+        }
+    }
+
+    fn client_detail(&self) -> &'static str {
+        match self {
+            UserError::RegisterFail => "User could not be registered",
+            UserError::RegisterFailNicknameCaptured =>
+                "Nickname is already taken by another user",
+            UserError::InvalidFields(_) => "Some fields have invalid values",
+            UserError::LoginFail => "User could not be logged in",
+            UserError::LoginFailInvalidParams => "Nickname or password is wrong",
+            UserError::AuthFail => "Authentication is required",
+            UserError::AdminRightsRequired => "Administrator rights are required",
+            UserError::EditFail => "User could not be edited",
+            UserError::EditFailNicknameCaptured =>
+                "Nickname is already taken by another user",
+            UserError::EmptyFieldToEdit => "No fields to edit were given",
+            UserError::DeleteFail => "User could not be deleted",
+            UserError::UserDoesNotExists => "User does not exist",
+            UserError::WrongPassword => "Current password is wrong",
         }
     }
 }
@@ -148,21 +326,128 @@ impl ToClientStatusAndError for NoteError {
         match self {
             NoteError::CreateFail
             | NoteError::ReceiveFail
+            | NoteError::SearchFail
+            | NoteError::ShareFail
+            | NoteError::LinkFail
             | NoteError::EditFail
             | NoteError::DeleteFail => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 ClientError::SERVICE_ERROR
             ),
-            NoteError::EditorCanNotEditNote
-            | NoteError::DeleterCanNotDeleteNote => (
+            NoteError::ReaderCanNotReadNote
+            | NoteError::SharerCanNotShareNote
+            | NoteError::EditorCanNotEditNote
+            | NoteError::EditorCanNotMoveNote
+            | NoteError::DeleterCanNotDeleteNote
+            | NoteError::LinkPasswordWrong => (
                 StatusCode::FORBIDDEN,
                 ClientError::NO_RIGHTS
             ),
 
-            NoteError::NoteDoesNotExists => (
+            NoteError::InvalidFields(_) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ClientError::INVALID_PARAMETERS
+            ),
+            NoteError::NoteDoesNotExists
+            | NoteError::RevisionDoesNotExists
+            | NoteError::TagDoesNotExists
+            | NoteError::ShareDoesNotExists
+            | NoteError::LinkDoesNotExists => (
                 StatusCode::NOT_FOUND,
                 ClientError::INVALID_PARAMETERS
-            )
+            ),
+            NoteError::LinkExpired => (
+                StatusCode::GONE,
+                ClientError::INVALID_PARAMETERS
+            ),
+            NoteError::LinkPasswordRequired => (
+                StatusCode::UNAUTHORIZED,
+                ClientError::NO_AUTHENTICATION
+            ),
+            NoteError::TagAlreadyExists => (
+                StatusCode::CONFLICT,
+                ClientError::INVALID_PARAMETERS
+            ),
+            NoteError::LinkPasswordAttemptsExceeded => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ClientError::TOO_MANY_ATTEMPTS
+            ),
+            NoteError::VersionMismatch(_) => (
+                StatusCode::PRECONDITION_FAILED,
+                ClientError::PRECONDITION_FAILED
+            ),
+        }
+    }
+
+    fn client_detail(&self) -> &'static str {
+        match self {
+            NoteError::CreateFail => "Note could not be created",
+            NoteError::InvalidFields(_) => "Some fields have invalid values",
+            NoteError::ReceiveFail => "Notes could not be received",
+            NoteError::ReaderCanNotReadNote => "You are not allowed to read this note",
+            NoteError::SearchFail => "Notes could not be searched",
+            NoteError::RevisionDoesNotExists => "Revision does not exist",
+            NoteError::TagDoesNotExists => "Tag does not exist",
+            NoteError::TagAlreadyExists => "Tag with this name already exists",
+            NoteError::ShareFail => "Note sharing could not be changed",
+            NoteError::SharerCanNotShareNote => "You are not allowed to share this note",
+            NoteError::ShareDoesNotExists => "Share does not exist",
+            NoteError::LinkFail => "Public link could not be changed",
+            NoteError::LinkDoesNotExists => "Public link does not exist",
+            NoteError::LinkExpired => "Public link has expired",
+            NoteError::LinkPasswordRequired => "Public link is protected by a password",
+            NoteError::LinkPasswordWrong => "Password of the public link is wrong",
+            NoteError::LinkPasswordAttemptsExceeded =>
+                "Too many wrong passwords for the public link, try again later",
+            NoteError::EditFail => "Note could not be edited",
+            NoteError::EditorCanNotEditNote => "You are not allowed to edit this note",
+            NoteError::EditorCanNotMoveNote =>
+                "Only the owner of the note can move it to another notebook",
+            NoteError::VersionMismatch(_) =>
+                "Note was changed since the given version, merge with the current one",
+            NoteError::DeleteFail => "Note could not be deleted",
+            NoteError::DeleterCanNotDeleteNote =>
+                "You are not allowed to delete this note",
+            NoteError::NoteDoesNotExists => "Note does not exist",
+        }
+    }
+}
+
+impl ToClientStatusAndError for NotebookError {
+    fn client_status_and_error(&self) -> (StatusCode, ClientError) {
+        match self {
+            NotebookError::CreateFail
+            | NotebookError::ReceiveFail
+            | NotebookError::EditFail
+            | NotebookError::DeleteFail => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ClientError::SERVICE_ERROR
+            ),
+            NotebookError::InvalidFields(_) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ClientError::INVALID_PARAMETERS
+            ),
+            NotebookError::MoveIntoOwnSubtree => (
+                StatusCode::CONFLICT,
+                ClientError::INVALID_PARAMETERS
+            ),
+            NotebookError::NotebookDoesNotExists => (
+                StatusCode::NOT_FOUND,
+                ClientError::INVALID_PARAMETERS
+            ),
+        }
+    }
+
+    fn client_detail(&self) -> &'static str {
+        match self {
+            NotebookError::CreateFail => "Notebook could not be created",
+            NotebookError::InvalidFields(_) => "Some fields have invalid values",
+            NotebookError::ReceiveFail => "Notebooks could not be received",
+            NotebookError::EditFail => "Notebook could not be edited",
+            NotebookError::MoveIntoOwnSubtree =>
+                "Notebook can not be moved into itself or its descendants",
+            NotebookError::DeleteFail => "Notebook could not be deleted",
+            NotebookError::NotebookDoesNotExists => "Notebook does not exist",
         }
     }
 }
@@ -171,8 +456,10 @@ impl ToClientStatusAndError for SessionError {
     fn client_status_and_error(&self) -> (StatusCode, ClientError) {
         match self {
             SessionError::CreateFail
+            | SessionError::ReceiveFail
             | SessionError::DeleteFail
-            | SessionError::ValidityCheckFail => (
+            | SessionError::ValidityCheckFail
+            | SessionError::RefreshFail => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 ClientError::SERVICE_ERROR
             ),
@@ -180,12 +467,76 @@ impl ToClientStatusAndError for SessionError {
                 StatusCode::NOT_FOUND,
                 ClientError::INVALID_PARAMETERS
             ),
+            SessionError::RefreshTokenInvalid
+            | SessionError::RefreshTokenReused => (
+                StatusCode::UNAUTHORIZED,
+                ClientError::NO_AUTHENTICATION
+            ),
             SessionError::SessionDoesNotExists => (
                 StatusCode::NOT_FOUND,
                 ClientError::INVALID_PARAMETERS
             ),
         }
     }
+
+    fn client_detail(&self) -> &'static str {
+        match self {
+            SessionError::CreateFail => "Session could not be created",
+            SessionError::ReceiveFail => "Sessions could not be received",
+            SessionError::DeleteFail => "Session could not be deleted",
+            SessionError::ValidityCheckFail => "Session could not be checked",
+            SessionError::SessionInvalid => "Session is no longer valid",
+            SessionError::RefreshFail => "Session could not be refreshed",
+            SessionError::RefreshTokenInvalid => "Refresh token is not valid",
+            SessionError::RefreshTokenReused =>
+                "Refresh token was already used, the session is ended",
+            SessionError::SessionDoesNotExists => "Session does not exist",
+        }
+    }
+}
+
+impl ToClientStatusAndError for RequestError {
+    fn client_status_and_error(&self) -> (StatusCode, ClientError) {
+        match self {
+            RequestError::MalformedBody
+            | RequestError::InvalidParameters(_) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::INVALID_PARAMETERS
+            ),
+            RequestError::UnsupportedMediaType => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                ClientError::INVALID_PARAMETERS
+            ),
+            RequestError::BodyTooLarge => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ClientError::INVALID_PARAMETERS
+            ),
+            RequestError::InvalidFields(_) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ClientError::INVALID_PARAMETERS
+            ),
+            RequestError::RouteDoesNotExists => (
+                StatusCode::NOT_FOUND,
+                ClientError::INVALID_PARAMETERS
+            ),
+            RequestError::MethodNotAllowed => (
+                StatusCode::METHOD_NOT_ALLOWED,
+                ClientError::INVALID_PARAMETERS
+            ),
+        }
+    }
+
+    fn client_detail(&self) -> &'static str {
+        match self {
+            RequestError::MalformedBody => "Request body could not be parsed",
+            RequestError::UnsupportedMediaType => "Request body has an unsupported content type",
+            RequestError::BodyTooLarge => "Request body is too large",
+            RequestError::InvalidFields(_) => "Some fields have invalid values",
+            RequestError::InvalidParameters(_) => "Some parameters have invalid values",
+            RequestError::RouteDoesNotExists => "Route does not exist",
+            RequestError::MethodNotAllowed => "Method is not allowed on this route",
+        }
+    }
 }
 
 #[allow(non_camel_case_types)]
@@ -196,5 +547,22 @@ pub enum ClientError {
     NO_AUTHENTICATION,
     NO_RIGHTS,
     INVALID_PARAMETERS,
+    PRECONDITION_FAILED,
+    TOO_MANY_ATTEMPTS,
     SERVICE_ERROR,
+}
+
+impl ClientError {
+    pub fn title(&self) -> &'static str {
+        match self {
+            ClientError::REGISTER_FAIL => "Registration failed",
+            ClientError::LOGIN_FAIL => "Login failed",
+            ClientError::NO_AUTHENTICATION => "Not authenticated",
+            ClientError::NO_RIGHTS => "Not allowed",
+            ClientError::INVALID_PARAMETERS => "Invalid parameters",
+            ClientError::PRECONDITION_FAILED => "Precondition failed",
+            ClientError::TOO_MANY_ATTEMPTS => "Too many attempts",
+            ClientError::SERVICE_ERROR => "Service error",
+        }
+    }
 }
\ No newline at end of file
@@ -18,6 +18,7 @@ pub fn log_layer(layer: &str, message: &str) {
 }
 
 pub async fn log_request(
+    request_id: &str,
     request_method: Method,
     uri: Uri,
     context: Option<&AuthTokenContext>,
@@ -25,6 +26,7 @@ pub async fn log_request(
     client_error: Option<ClientError>,
 ) {
     let request = Request {
+        request_id: request_id.to_string(),
         user_id: context.map(|context| context.user_id()),
         request_path: uri.to_string(),
         request_method: request_method.to_string(),
@@ -38,6 +40,7 @@ pub async fn log_request(
 }
 
 struct Request {
+    request_id: String,
     user_id: Option<u32>,
 
     request_path: String,
@@ -61,20 +64,26 @@ impl Display for Request {
             self.request_method
         )?;
 
-        if self.user_id.is_some() {
+        writeln!(
+            formatter,
+            "Request ID: {};",
+            self.request_id
+        )?;
+
+        if let Some(user_id) = self.user_id {
             writeln!(
                 formatter,
                 "User ID: {};",
-                self.user_id.unwrap()
+                user_id
             )?
         }
 
-        if self.service_error.is_some() {
+        if let Some(service_error) = self.service_error.as_ref() {
             writeln!(
                 formatter,
                 "Error: {};\n\
                 Client error: {}.",
-                self.service_error.as_ref().unwrap(),
+                service_error,
                 self.client_error.as_ref().unwrap()
             )?;
         }
@@ -1,3 +1,5 @@
+use std::net::SocketAddr;
+
 use tokio::net::TcpListener;
 
 use crate::error::Result;
@@ -14,7 +16,7 @@ mod web;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let application_state = ApplicationState::new();
+    let application_state = ApplicationState::new().await?;
 
     let listener =
         TcpListener::bind(application_state.settings.server.address())
@@ -25,8 +27,11 @@ async fn main() -> Result<()> {
         application_state.settings.server.address().as_str()
     );
     
-    axum::serve(listener, web::routes(application_state))
-        .await.unwrap();
+    axum::serve(
+        listener,
+        web::routes(application_state)
+            .into_make_service_with_connect_info::<SocketAddr>(),
+    ).await.unwrap();
     
     Ok(())
 }
@@ -1,20 +1,55 @@
-use crate::model::notes::notes_service::NotesService;
+use crate::error::Result;
+use crate::model::links::links_service::LinksService;
+use crate::model::notebooks::notebooks_service::NotebooksService;
+use crate::model::notes::notes_service::{purge_trash_periodically, NotesService};
+use crate::model::revisions::revisions_service::RevisionsService;
 use crate::model::sessions::sessions_service::SessionsService;
+use crate::model::shares::shares_service::SharesService;
+use crate::model::storage::Repositories;
 use crate::model::users::users_service::UsersService;
+use crate::model::users::password_hashing::PasswordHashing;
+use crate::settings::Settings;
 
 #[derive(Clone)]
 pub struct Database {
     pub users: UsersService,
     pub notes: NotesService,
+    pub notebooks: NotebooksService,
+    pub links: LinksService,
     pub sessions: SessionsService,
 }
 
 impl Database {
-    pub fn new() -> Self {
-        Self {
-            users: UsersService::new(),
-            notes: NotesService::new(),
-            sessions: SessionsService::new(),
-        }
+    pub async fn new(settings: &Settings) -> Result<Self> {
+        let repositories = Repositories::new(&settings.storage);
+        let passwords = PasswordHashing::new(&settings.password);
+
+        let notes = NotesService::new(
+            repositories.notes,
+            repositories.notebooks.clone(),
+            RevisionsService::new(repositories.revisions, settings.revisions.retention),
+            SharesService::new(
+                repositories.shares,
+                repositories.share_events,
+                repositories.users.clone(),
+            ),
+            repositories.links.clone(),
+        ).await?;
+
+        tokio::spawn(purge_trash_periodically(notes.clone(), settings.trash.clone()));
+
+        let users = UsersService::new(repositories.users, passwords.clone());
+        users.promote_admins(&settings.users.admins).await?;
+
+        Ok(Self {
+            users,
+            notebooks: NotebooksService::new(repositories.notebooks, notes.clone()),
+            links: LinksService::new(repositories.links, notes.clone(), passwords),
+            notes,
+            sessions: SessionsService::new(
+                repositories.sessions,
+                repositories.refresh_tokens,
+            ),
+        })
     }
-}
\ No newline at end of file
+}
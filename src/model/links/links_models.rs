@@ -0,0 +1,110 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::model::notes::notes_models::Note;
+use crate::model::notes::notes_query::invalid_field;
+
+const MAX_PASSWORD_LENGTH: usize = 128;
+
+/// Public read-only link to a note, opened without an account.
+/// Persisted as is, responses use `NoteLinkDetails`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NoteLink {
+    pub id: u64,
+    pub note_id: u64,
+    pub owner_id: u32,
+    pub token: String,
+    pub password_hash: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct NewNoteLink {
+    pub note_id: u64,
+    pub owner_id: u32,
+    pub token: String,
+    pub password_hash: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct NoteLinkCreate {
+    pub password: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct NoteLinkDetails {
+    pub id: u64,
+    pub note_id: u64,
+    pub token: String,
+    pub has_password: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Password of a protected link, sent as a form.
+#[derive(Deserialize)]
+pub struct NoteLinkUnlock {
+    pub password: String,
+}
+
+/// Note as shown through a public link, without anything
+/// about its owner or its place among the owner's notes.
+#[derive(Serialize)]
+pub struct PublicNote {
+    pub title: String,
+    pub body: String,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NoteLinkCreate {
+    pub fn validate(&self) -> Result<()> {
+        if self.password.as_ref().is_some_and(|password| {
+            password.is_empty() || password.chars().count() > MAX_PASSWORD_LENGTH
+        }) {
+            return Err(invalid_field("password", "Password must be 1 to 128 characters long"));
+        }
+
+        if self.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+            return Err(invalid_field("expires_at", "Expiry must be in the future"));
+        }
+
+        Ok(())
+    }
+}
+
+impl NoteLink {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= Utc::now())
+    }
+}
+
+impl From<NoteLink> for NoteLinkDetails {
+    fn from(link: NoteLink) -> Self {
+        NoteLinkDetails {
+            id: link.id,
+            note_id: link.note_id,
+            token: link.token,
+            has_password: link.password_hash.is_some(),
+            expires_at: link.expires_at,
+            created_at: link.created_at,
+        }
+    }
+}
+
+impl From<Note> for PublicNote {
+    fn from(note: Note) -> Self {
+        PublicNote {
+            title: note.title,
+            body: note.body,
+            tags: note.tags,
+            created_at: note.created_at,
+            updated_at: note.updated_at,
+        }
+    }
+}
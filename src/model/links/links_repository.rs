@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+
+use crate::error::StorageResult;
+use crate::model::links::links_models::{NewNoteLink, NoteLink};
+
+#[async_trait]
+pub trait LinksRepository: Send + Sync {
+    async fn insert_link(&self, new_link: NewNoteLink) -> StorageResult<NoteLink>;
+
+    async fn link_by_id(&self, link_id: u64) -> StorageResult<Option<NoteLink>>;
+
+    async fn link_by_token(&self, token: &str) -> StorageResult<Option<NoteLink>>;
+
+    async fn links_by_note(&self, note_id: u64) -> StorageResult<Vec<NoteLink>>;
+
+    async fn delete_link(&self, link_id: u64) -> StorageResult<Option<NoteLink>>;
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::context::AuthTokenContext;
+use crate::error::{Error, NoteError, Result};
+use crate::model::links::links_models::{
+    NewNoteLink,
+    NoteLinkCreate,
+    NoteLinkDetails,
+    PublicNote,
+};
+use crate::model::links::links_repository::LinksRepository;
+use crate::model::notes::notes_service::NotesService;
+use crate::model::tokens::generate_token;
+use crate::model::users::password_hashing::{PasswordCheck, PasswordHashing};
+
+//Wrong passwords a protected link accepts before it is locked
+const MAX_PASSWORD_ATTEMPTS: u32 = 5;
+//Minutes a link stays locked, counted from its first wrong password
+const PASSWORD_LOCK_MINUTES: i64 = 15;
+
+/// Public links to notes. Managing the links of a note takes the same
+/// rights as sharing it, opening a link takes only its token.
+#[derive(Clone)]
+pub struct LinksService {
+    repository: Arc<dyn LinksRepository>,
+    notes: NotesService,
+    passwords: PasswordHashing,
+    password_attempts: Arc<Mutex<HashMap<u64, PasswordAttempts>>>,
+}
+
+/// Password attempts on a link since the first one, which are
+/// forgotten after a correct password.
+struct PasswordAttempts {
+    count: u32,
+    first_at: DateTime<Utc>,
+}
+
+impl LinksService {
+    pub fn new(
+        repository: Arc<dyn LinksRepository>,
+        notes: NotesService,
+        passwords: PasswordHashing,
+    ) -> Self {
+        Self {
+            repository,
+            notes,
+            passwords,
+            password_attempts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl LinksService {
+    pub async fn create_link(
+        &self, note_id: u64, link_create: NoteLinkCreate, owner: &AuthTokenContext,
+    ) -> Result<NoteLinkDetails> {
+        link_create.validate()?;
+
+        let note = self.notes.shareable_note(note_id, owner).await?;
+
+        let password_hash = match link_create.password {
+            Some(password) => Some(
+                self.passwords.hash(password).await
+                    .ok_or(Error::Notes(NoteError::LinkFail))?
+            ),
+            None => None,
+        };
+
+        let new_link = NewNoteLink {
+            note_id: note.id,
+            owner_id: note.creator_id,
+            token: generate_token(),
+            password_hash,
+            expires_at: link_create.expires_at,
+            created_at: Utc::now(),
+        };
+
+        let link = self.repository.insert_link(new_link).await
+            .map_err(|_| Error::Notes(NoteError::LinkFail))?;
+
+        Ok(link.into())
+    }
+
+    pub async fn links_of_note(
+        &self, note_id: u64, owner: &AuthTokenContext,
+    ) -> Result<Vec<NoteLinkDetails>> {
+        let note = self.notes.shareable_note(note_id, owner).await?;
+
+        let links = self.repository.links_by_note(note.id).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))?
+            .into_iter()
+            .map(NoteLinkDetails::from)
+            .collect();
+
+        Ok(links)
+    }
+
+    pub async fn revoke_link(
+        &self, note_id: u64, link_id: u64, owner: &AuthTokenContext,
+    ) -> Result<NoteLinkDetails> {
+        let note = self.notes.shareable_note(note_id, owner).await?;
+
+        self.repository.link_by_id(link_id).await
+            .map_err(|_| Error::Notes(NoteError::LinkFail))?
+            .filter(|link| link.note_id == note.id)
+            .ok_or(Error::Notes(NoteError::LinkDoesNotExists))?;
+
+        let link = self.repository.delete_link(link_id).await
+            .map_err(|_| Error::Notes(NoteError::LinkFail))?
+            .ok_or(Error::Notes(NoteError::LinkDoesNotExists))?;
+
+        Ok(link.into())
+    }
+
+    /// Note behind the link, as long as the link is not expired
+    /// and the password matches for protected links.
+    pub async fn public_note(
+        &self, token: &str, password: Option<String>,
+    ) -> Result<PublicNote> {
+        let link = self.repository.link_by_token(token).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))?
+            .ok_or(Error::Notes(NoteError::LinkDoesNotExists))?;
+
+        if link.is_expired() {
+            return Err(Error::Notes(NoteError::LinkExpired));
+        }
+
+        if let Some(password_hash) = link.password_hash {
+            let password = password
+                .ok_or(Error::Notes(NoteError::LinkPasswordRequired))?;
+
+            self.reserve_attempt(link.id)?;
+
+            let check = self.passwords.verify(password, password_hash).await;
+
+            if let PasswordCheck::Invalid = check {
+                return Err(Error::Notes(NoteError::LinkPasswordWrong));
+            }
+
+            self.password_attempts()?.remove(&link.id);
+        }
+
+        let note = self.notes.linked_note(link.note_id).await?;
+
+        Ok(note.into())
+    }
+
+    /// Counts an attempt before the password is checked, so that parallel
+    /// guesses can not slip past the limit. Attempts older than the lock
+    /// period are forgotten, which opens a locked link again.
+    fn reserve_attempt(&self, link_id: u64) -> Result<()> {
+        let mut password_attempts = self.password_attempts()?;
+        let now = Utc::now();
+
+        let attempts = password_attempts
+            .entry(link_id)
+            .or_insert(PasswordAttempts { count: 0, first_at: now });
+
+        if attempts.first_at + Duration::minutes(PASSWORD_LOCK_MINUTES) <= now {
+            *attempts = PasswordAttempts { count: 0, first_at: now };
+        }
+
+        if attempts.count >= MAX_PASSWORD_ATTEMPTS {
+            return Err(Error::Notes(NoteError::LinkPasswordAttemptsExceeded));
+        }
+
+        attempts.count += 1;
+
+        Ok(())
+    }
+
+    fn password_attempts(&self) -> Result<MutexGuard<'_, HashMap<u64, PasswordAttempts>>> {
+        self.password_attempts.lock()
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))
+    }
+}
@@ -0,0 +1,3 @@
+pub mod links_models;
+pub mod links_repository;
+pub mod links_service;
@@ -1,4 +1,12 @@
 pub mod database;
+pub mod links;
+pub mod notebooks;
 pub mod notes;
+pub mod policy;
+pub mod revisions;
+pub mod search;
 pub mod sessions;
+pub mod shares;
+pub mod storage;
+pub mod tokens;
 pub mod users;
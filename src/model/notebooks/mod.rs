@@ -0,0 +1,3 @@
+pub mod notebooks_models;
+pub mod notebooks_repository;
+pub mod notebooks_service;
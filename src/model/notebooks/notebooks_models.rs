@@ -0,0 +1,107 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::{Error, FieldError, NotebookError, Result};
+
+const MAX_NAME_LENGTH: usize = 100;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Notebook {
+    pub id: u64,
+    pub owner_id: u32,
+    /// Root notebooks have no parent
+    pub parent_id: Option<u64>,
+    pub name: String,
+    /// Order among the siblings, ascending
+    pub position: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct NewNotebook {
+    pub owner_id: u32,
+    pub parent_id: Option<u64>,
+    pub name: String,
+    pub position: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct NotebookCreate {
+    pub name: String,
+    pub parent_id: Option<u64>,
+    /// Defaults to the position after the last sibling
+    pub position: Option<i64>,
+}
+
+/// Renames, reorders or moves a notebook. `"parent_id": null` moves it
+/// to the root, a missing `parent_id` keeps the current parent.
+#[derive(Deserialize)]
+pub struct NotebookEdit {
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub parent_id: Option<Option<u64>>,
+    pub position: Option<i64>,
+}
+
+/// What happens to the notes of a deleted notebook.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletedNotebookNotes {
+    /// Notes and child notebooks are moved to the parent notebook
+    #[default]
+    Rehome,
+    /// Child notebooks of the whole subtree are deleted,
+    /// their notes are moved into the trash
+    Cascade,
+}
+
+#[derive(Deserialize)]
+pub struct NotebookDelete {
+    #[serde(default)]
+    pub notes: DeletedNotebookNotes,
+}
+
+#[derive(Serialize)]
+pub struct NotebookTree {
+    #[serde(flatten)]
+    pub notebook: Notebook,
+    pub children: Vec<NotebookTree>,
+}
+
+impl NotebookCreate {
+    pub fn validate(&self) -> Result<()> {
+        validate_name(&self.name)
+    }
+}
+
+impl NotebookEdit {
+    pub fn validate(&self) -> Result<()> {
+        match self.name.as_ref() {
+            Some(name) => validate_name(name),
+            None => Ok(()),
+        }
+    }
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let length = name.trim().chars().count();
+
+    if length == 0 || length > MAX_NAME_LENGTH {
+        return Err(Error::Notebooks(NotebookError::InvalidFields(vec![FieldError {
+            field: "name",
+            message: "Name must be 1 to 100 characters long",
+        }])));
+    }
+
+    Ok(())
+}
+
+/// Tells an explicit `null` apart from a missing field
+/// when used together with `#[serde(default)]`.
+pub fn deserialize_some<'de, T, D>(deserializer: D) -> core::result::Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+
+use crate::error::StorageResult;
+use crate::model::notebooks::notebooks_models::{NewNotebook, Notebook};
+
+#[async_trait]
+pub trait NotebooksRepository: Send + Sync {
+    async fn insert_notebook(
+        &self, new_notebook: NewNotebook,
+    ) -> StorageResult<Notebook>;
+
+    async fn notebook_by_id(
+        &self, notebook_id: u64,
+    ) -> StorageResult<Option<Notebook>>;
+
+    async fn notebooks_by_owner(&self, owner_id: u32) -> StorageResult<Vec<Notebook>>;
+
+    async fn update_notebook(
+        &self, notebook: Notebook,
+    ) -> StorageResult<Option<Notebook>>;
+
+    async fn delete_notebook(
+        &self, notebook_id: u64,
+    ) -> StorageResult<Option<Notebook>>;
+}
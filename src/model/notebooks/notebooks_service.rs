@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::context::AuthTokenContext;
+use crate::error::{Error, NotebookError, Result};
+use crate::model::notebooks::notebooks_models::{
+    DeletedNotebookNotes,
+    NewNotebook,
+    Notebook,
+    NotebookCreate,
+    NotebookEdit,
+    NotebookTree,
+};
+use crate::model::notebooks::notebooks_repository::NotebooksRepository;
+use crate::model::notes::notes_query::{NotesPage, NotesQuery};
+use crate::model::notes::notes_service::NotesService;
+
+#[derive(Clone)]
+pub struct NotebooksService {
+    repository: Arc<dyn NotebooksRepository>,
+    notes: NotesService,
+}
+
+impl NotebooksService {
+    pub fn new(repository: Arc<dyn NotebooksRepository>, notes: NotesService) -> Self {
+        Self { repository, notes }
+    }
+}
+
+impl NotebooksService {
+    pub async fn create_notebook(
+        &self, owner: &AuthTokenContext, notebook_create: NotebookCreate,
+    ) -> Result<Notebook> {
+        notebook_create.validate()?;
+
+        if let Some(parent_id) = notebook_create.parent_id {
+            self.owned_notebook(owner, parent_id).await?;
+        }
+
+        let position = match notebook_create.position {
+            Some(position) => position,
+            None => self.notebooks(owner).await?
+                .iter()
+                .filter(|notebook| notebook.parent_id == notebook_create.parent_id)
+                .map(|notebook| notebook.position + 1)
+                .max()
+                .unwrap_or_default(),
+        };
+
+        let new_notebook = NewNotebook {
+            owner_id: owner.user_id(),
+            parent_id: notebook_create.parent_id,
+            name: notebook_create.name.trim().to_string(),
+            position,
+            created_at: Utc::now(),
+        };
+
+        self.repository.insert_notebook(new_notebook).await
+            .map_err(|_| Error::Notebooks(NotebookError::CreateFail))
+    }
+
+    /// All notebooks of the owner as a forest, siblings ordered by position.
+    pub async fn tree(&self, owner: &AuthTokenContext) -> Result<Vec<NotebookTree>> {
+        let notebooks = self.notebooks(owner).await?;
+
+        let mut children = HashMap::<Option<u64>, Vec<Notebook>>::new();
+        for notebook in notebooks {
+            children.entry(notebook.parent_id).or_default().push(notebook);
+        }
+
+        Ok(build_tree(&mut children, None))
+    }
+
+    pub async fn edit_notebook(
+        &self, owner: &AuthTokenContext, notebook_id: u64, notebook_edit: NotebookEdit,
+    ) -> Result<Notebook> {
+        notebook_edit.validate()?;
+
+        let notebook = self.owned_notebook(owner, notebook_id).await?;
+
+        if let Some(Some(parent_id)) = notebook_edit.parent_id {
+            self.owned_notebook(owner, parent_id).await?;
+
+            let notebooks = self.notebooks(owner).await?;
+            if subtree(&notebooks, notebook_id).contains(&parent_id) {
+                return Err(Error::Notebooks(NotebookError::MoveIntoOwnSubtree));
+            }
+        }
+
+        let edited_notebook = Notebook {
+            name: notebook_edit.name
+                .map(|name| name.trim().to_string())
+                .unwrap_or(notebook.name),
+            parent_id: notebook_edit.parent_id.unwrap_or(notebook.parent_id),
+            position: notebook_edit.position.unwrap_or(notebook.position),
+            ..notebook
+        };
+
+        self.repository.update_notebook(edited_notebook).await
+            .map_err(|_| Error::Notebooks(NotebookError::EditFail))?
+            .ok_or(Error::Notebooks(NotebookError::NotebookDoesNotExists))
+    }
+
+    pub async fn delete_notebook(
+        &self, owner: &AuthTokenContext, notebook_id: u64, notes: DeletedNotebookNotes,
+    ) -> Result<Notebook> {
+        let notebook = self.owned_notebook(owner, notebook_id).await?;
+        let notebooks = self.notebooks(owner).await?;
+
+        let deleted_notebooks = match notes {
+            DeletedNotebookNotes::Rehome => {
+                self.notes
+                    .rehome_notes(owner, &[notebook_id], notebook.parent_id)
+                    .await?;
+
+                for child in notebooks.into_iter()
+                    .filter(|child| child.parent_id == Some(notebook_id))
+                {
+                    let child = Notebook { parent_id: notebook.parent_id, ..child };
+
+                    self.repository.update_notebook(child).await
+                        .map_err(|_| Error::Notebooks(NotebookError::DeleteFail))?;
+                }
+
+                vec![notebook_id]
+            }
+            DeletedNotebookNotes::Cascade => {
+                let deleted_notebooks = subtree(&notebooks, notebook_id);
+
+                self.notes.trash_notes_in(owner, &deleted_notebooks).await?;
+
+                deleted_notebooks
+            }
+        };
+
+        // Children first, so no notebook is left pointing to a deleted parent
+        for deleted_notebook in deleted_notebooks.iter().rev() {
+            self.repository.delete_notebook(*deleted_notebook).await
+                .map_err(|_| Error::Notebooks(NotebookError::DeleteFail))?;
+        }
+
+        Ok(notebook)
+    }
+
+    /// Notes of the notebook and of all its descendants.
+    pub async fn page_of_notes(
+        &self, owner: &AuthTokenContext, notebook_id: u64, query: NotesQuery,
+    ) -> Result<NotesPage> {
+        self.owned_notebook(owner, notebook_id).await?;
+
+        let notebooks = self.notebooks(owner).await?;
+        let subtree = subtree(&notebooks, notebook_id);
+
+        self.notes.page_of_notes_in(owner, subtree, query).await
+    }
+
+    /// Deletes the notes and notebooks of a deleted user, so that nothing
+    /// of theirs stays reachable, through public links neither.
+    pub async fn delete_content_of_user(&self, user_id: u32) -> Result<()> {
+        self.notes.purge_notes_of_user(user_id).await?;
+
+        let notebooks = self.repository.notebooks_by_owner(user_id).await
+            .map_err(|_| Error::Notebooks(NotebookError::DeleteFail))?;
+
+        for root in notebooks.iter().filter(|notebook| notebook.parent_id.is_none()) {
+            // Children first, so no notebook is left pointing to a deleted parent
+            for notebook_id in subtree(&notebooks, root.id).iter().rev() {
+                self.repository.delete_notebook(*notebook_id).await
+                    .map_err(|_| Error::Notebooks(NotebookError::DeleteFail))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn notebooks(&self, owner: &AuthTokenContext) -> Result<Vec<Notebook>> {
+        self.repository.notebooks_by_owner(owner.user_id()).await
+            .map_err(|_| Error::Notebooks(NotebookError::ReceiveFail))
+    }
+
+    /// Notebooks of other users are reported as missing.
+    async fn owned_notebook(
+        &self, owner: &AuthTokenContext, notebook_id: u64,
+    ) -> Result<Notebook> {
+        self.repository.notebook_by_id(notebook_id).await
+            .map_err(|_| Error::Notebooks(NotebookError::ReceiveFail))?
+            .filter(|notebook| notebook.owner_id == owner.user_id())
+            .ok_or(Error::Notebooks(NotebookError::NotebookDoesNotExists))
+    }
+}
+
+fn build_tree(
+    children: &mut HashMap<Option<u64>, Vec<Notebook>>, parent_id: Option<u64>,
+) -> Vec<NotebookTree> {
+    let mut notebooks = children.remove(&parent_id).unwrap_or_default();
+
+    notebooks.sort_by(|first, second| first.position.cmp(&second.position)
+        .then_with(|| first.name.cmp(&second.name))
+        .then(first.id.cmp(&second.id))
+    );
+
+    notebooks.into_iter()
+        .map(|notebook| NotebookTree {
+            children: build_tree(children, Some(notebook.id)),
+            notebook,
+        })
+        .collect()
+}
+
+/// Ids of the notebook and its descendants, every parent before its children.
+fn subtree(notebooks: &[Notebook], notebook_id: u64) -> Vec<u64> {
+    let mut subtree = vec![notebook_id];
+    let mut index = 0;
+
+    while let Some(parent_id) = subtree.get(index).copied() {
+        subtree.extend(notebooks.iter()
+            .filter(|notebook| notebook.parent_id == Some(parent_id))
+            .map(|notebook| notebook.id)
+        );
+        index += 1;
+    }
+
+    subtree
+}
@@ -1,2 +1,5 @@
 pub mod notes_models;
-pub mod notes_service;
\ No newline at end of file
+pub mod notes_query;
+pub mod notes_repository;
+pub mod notes_service;
+pub mod notes_tags;
\ No newline at end of file
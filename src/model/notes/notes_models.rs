@@ -1,22 +1,76 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize)]
+use crate::model::notebooks::notebooks_models::deserialize_some;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Note {
     pub id: u64,
     pub creator_id: u32,
     pub title: String,
     pub body: String,
+    #[serde(default)]
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_editor_id: u32,
+    /// Normalized tags of the note creator, sorted by name
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Notebook of the note creator, notes without one are at the root
+    #[serde(default)]
+    pub notebook_id: Option<u64>,
+    /// Incremented by every change, exposed as the `ETag`
+    #[serde(default = "initial_note_version")]
+    pub version: u64,
+    /// Set while the note is in the trash
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+pub const INITIAL_NOTE_VERSION: u64 = 1;
+
+pub struct NewNote {
+    pub creator_id: u32,
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub tags: Vec<String>,
+    pub notebook_id: Option<u64>,
 }
 
 #[derive(Deserialize)]
 pub struct NoteCreate {
     pub title: String,
     pub body: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub notebook_id: Option<u64>,
 }
 
 #[derive(Deserialize)]
 pub struct NoteEdit {
-    pub id: u64,
     pub title: Option<String>,
     pub body: Option<String>,
-}
\ No newline at end of file
+    /// Replaces all tags of the note
+    pub tags: Option<Vec<String>>,
+    /// `null` moves the note out of its notebook
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub notebook_id: Option<Option<u64>>,
+    /// Version the edit is based on, stale edits are rejected.
+    /// The `If-Match` header takes precedence over it
+    pub expected_version: Option<u64>,
+}
+
+/// Body of the legacy `/notes/edit` route, which addresses the note
+/// in the body instead of the path.
+#[derive(Deserialize)]
+pub struct NoteEditById {
+    pub id: u64,
+    #[serde(flatten)]
+    pub note_edit: NoteEdit,
+}
+fn initial_note_version() -> u64 {
+    INITIAL_NOTE_VERSION
+}
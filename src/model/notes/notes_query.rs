@@ -0,0 +1,398 @@
+use std::cmp::Ordering;
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, FieldError, NoteError, Result};
+use crate::model::notes::notes_models::Note;
+use crate::model::notes::notes_tags::{normalize_tag, TAG_SEPARATOR};
+use crate::model::search::SnippetFragment;
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 200;
+
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+const MAX_SEARCH_LIMIT: usize = 100;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotesSort {
+    #[default]
+    Created,
+    Updated,
+    Title,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// How notes are matched against several tags of the `tag` filter.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMode {
+    /// Note has every tag
+    #[default]
+    And,
+    /// Note has at least one of the tags
+    Or,
+}
+
+/// Query string of the notes list.
+#[derive(Deserialize)]
+pub struct NotesQuery {
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub sort: NotesSort,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// Case insensitive substring of the title
+    pub title: Option<String>,
+    /// Inclusive lower bound of the creation time
+    pub created_from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound of the creation time
+    pub created_to: Option<DateTime<Utc>>,
+    /// Inclusive lower bound of the last update time
+    pub updated_from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound of the last update time
+    pub updated_to: Option<DateTime<Utc>>,
+    /// Comma separated tags
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub tag_mode: TagMode,
+}
+
+#[derive(Serialize)]
+pub struct NotesPage {
+    pub notes: Vec<Note>,
+    pub next_cursor: Option<String>,
+    pub total: usize,
+}
+
+/// Query string of the notes search.
+#[derive(Deserialize)]
+pub struct NotesSearchQuery {
+    /// Words, `"quoted phrases"` and `prefixes*`, all of them must match
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct NoteSearchResult {
+    pub note: Note,
+    pub score: f64,
+    /// Fragment of the body around the matches
+    pub snippet: Vec<SnippetFragment>,
+}
+
+/// Notes of one creator asked for by a validated `NotesQuery`, which
+/// `NotesRepository` filters, sorts and cuts. Trashed notes are left out.
+pub struct NotesFilter {
+    pub creator_id: u32,
+    /// Notes in one of the notebooks, all notes if not set
+    pub notebook_ids: Option<Vec<u64>>,
+    /// Lowercase substring of the title
+    pub title: Option<String>,
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+    pub updated_from: Option<DateTime<Utc>>,
+    pub updated_to: Option<DateTime<Utc>>,
+    pub tags: Option<Vec<String>>,
+    pub tag_mode: TagMode,
+    pub sort: NotesSort,
+    pub order: SortOrder,
+    /// Sort key and id of the note the notes follow
+    pub after: Option<(SortKey, u64)>,
+    /// Number of notes to return at most
+    pub limit: usize,
+}
+
+/// Notes returned for a `NotesFilter`.
+pub struct NotesSlice {
+    pub notes: Vec<Note>,
+    /// Number of notes matching the filter, whatever the cursor and limit
+    pub total: usize,
+}
+
+/// Position after the last returned note. Encoded as URL safe base64
+/// JSON, clients must treat it as opaque.
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    sort: NotesSort,
+    order: SortOrder,
+    key: SortKey,
+    id: u64,
+}
+
+/// Value notes are sorted by. Titles are compared lowercase.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SortKey {
+    Created(DateTime<Utc>),
+    Updated(DateTime<Utc>),
+    Title(String),
+}
+
+impl NotesQuery {
+    /// Validates the query into the filter of the creator's notes. One note
+    /// more than the page holds is asked for to know whether another follows.
+    pub fn filter(&self, creator_id: u32) -> Result<NotesFilter> {
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT);
+        if limit == 0 || limit > MAX_LIMIT {
+            return Err(invalid_field("limit", "Limit must be between 1 and 200"));
+        }
+
+        let after = match self.cursor.as_deref() {
+            Some(cursor) => {
+                let cursor = decode_cursor(cursor)?;
+
+                if cursor.sort != self.sort || cursor.order != self.order {
+                    return Err(invalid_field(
+                        "cursor",
+                        "Cursor belongs to another sort order",
+                    ));
+                }
+
+                Some((cursor.key, cursor.id))
+            }
+            None => None,
+        };
+
+        let tags = self.tag.as_deref()
+            .map(|tags| tags.split(TAG_SEPARATOR)
+                .map(normalize_tag)
+                .collect::<Result<Vec<_>>>()
+            )
+            .transpose()?;
+
+        Ok(NotesFilter {
+            creator_id,
+            notebook_ids: None,
+            title: self.title.as_ref().map(|title| title.to_lowercase()),
+            created_from: self.created_from,
+            created_to: self.created_to,
+            updated_from: self.updated_from,
+            updated_to: self.updated_to,
+            tags,
+            tag_mode: self.tag_mode,
+            sort: self.sort,
+            order: self.order,
+            after,
+            limit: limit + 1,
+        })
+    }
+}
+
+impl NotesFilter {
+    /// Page out of the notes returned for the filter.
+    pub fn page(&self, slice: NotesSlice) -> NotesPage {
+        let limit = self.limit - 1;
+        let mut notes = slice.notes;
+
+        let next_cursor = if notes.len() > limit {
+            notes.truncate(limit);
+            notes.last().map(|note| encode_cursor(&Cursor {
+                sort: self.sort,
+                order: self.order,
+                key: self.sort_key(note),
+                id: note.id,
+            }))
+        } else {
+            None
+        };
+
+        NotesPage {
+            notes,
+            next_cursor,
+            total: slice.total,
+        }
+    }
+
+    /// Whether the note passes the filter, apart from the cursor.
+    pub fn matches(&self, note: &Note) -> bool {
+        note.creator_id == self.creator_id
+            && note.deleted_at.is_none()
+            && self.notebook_ids.as_ref().is_none_or(|notebook_ids| note.notebook_id
+                .is_some_and(|notebook_id| notebook_ids.contains(&notebook_id))
+            )
+            && self.title.as_ref()
+                .is_none_or(|title| note.title.to_lowercase().contains(title))
+            && is_in_range(note.created_at, self.created_from, self.created_to)
+            && is_in_range(note.updated_at, self.updated_from, self.updated_to)
+            && self.tags.as_ref().is_none_or(|tags| self.has_tags(note, tags))
+    }
+
+    /// Whether the note comes after the cursor.
+    pub fn is_after_cursor(&self, note: &Note) -> bool {
+        self.after.as_ref().is_none_or(|(key, id)| {
+            self.compare(note, key, *id) == Ordering::Greater
+        })
+    }
+
+    /// Order of the note against the given sort key and id.
+    pub fn compare(&self, note: &Note, key: &SortKey, id: u64) -> Ordering {
+        let ordering = self.sort_key(note).cmp(key).then(note.id.cmp(&id));
+
+        match self.order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    }
+
+    pub fn sort_key(&self, note: &Note) -> SortKey {
+        match self.sort {
+            NotesSort::Created => SortKey::Created(note.created_at),
+            NotesSort::Updated => SortKey::Updated(note.updated_at),
+            NotesSort::Title => SortKey::Title(note.title.to_lowercase()),
+        }
+    }
+
+    fn has_tags(&self, note: &Note, tags: &[String]) -> bool {
+        match self.tag_mode {
+            TagMode::And => tags.iter().all(|tag| note.tags.contains(tag)),
+            TagMode::Or => tags.iter().any(|tag| note.tags.contains(tag)),
+        }
+    }
+}
+
+fn is_in_range(
+    time: DateTime<Utc>, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>,
+) -> bool {
+    from.is_none_or(|from| time >= from) && to.is_none_or(|to| time < to)
+}
+
+fn encode_cursor(cursor: &Cursor) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(cursor).unwrap_or_default())
+}
+
+fn decode_cursor(cursor: &str) -> Result<Cursor> {
+    URL_SAFE_NO_PAD.decode(cursor)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| invalid_field("cursor", "Cursor is malformed"))
+}
+
+impl NotesSearchQuery {
+    pub fn limit(&self) -> Result<usize> {
+        let limit = self.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+        if limit == 0 || limit > MAX_SEARCH_LIMIT {
+            return Err(invalid_field("limit", "Limit must be between 1 and 100"));
+        }
+
+        Ok(limit)
+    }
+}
+
+pub fn invalid_field(field: &'static str, message: &'static str) -> Error {
+    Error::Notes(NoteError::InvalidFields(vec![FieldError { field, message }]))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::model::notes::notes_models::NewNote;
+    use crate::model::notes::notes_repository::NotesRepository;
+    use crate::model::storage::memory::InMemoryNotesRepository;
+
+    const CREATOR_ID: u32 = 1;
+
+    async fn repository(titles: &[&str]) -> InMemoryNotesRepository {
+        let repository = InMemoryNotesRepository::default();
+        let start = Utc::now();
+
+        for (index, title) in titles.iter().enumerate() {
+            repository.insert_note(NewNote {
+                creator_id: CREATOR_ID,
+                title: title.to_string(),
+                body: String::new(),
+                created_at: start + Duration::seconds(index as i64),
+                tags: vec![],
+                notebook_id: None,
+            }).await.unwrap();
+        }
+
+        repository
+    }
+
+    fn query(limit: usize, order: SortOrder, cursor: Option<String>) -> NotesQuery {
+        NotesQuery {
+            cursor,
+            limit: Some(limit),
+            sort: NotesSort::Created,
+            order,
+            title: None,
+            created_from: None,
+            created_to: None,
+            updated_from: None,
+            updated_to: None,
+            tag: None,
+            tag_mode: TagMode::And,
+        }
+    }
+
+    async fn page(repository: &InMemoryNotesRepository, query: NotesQuery) -> NotesPage {
+        let filter = query.filter(CREATOR_ID).unwrap();
+
+        filter.page(repository.notes_by_filter(&filter).await.unwrap())
+    }
+
+    fn titles(page: &NotesPage) -> Vec<&str> {
+        page.notes.iter().map(|note| note.title.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn cursor_round_trips_through_all_pages() {
+        let repository = repository(&["a", "b", "c", "d", "e"]).await;
+
+        let first = page(&repository, query(2, SortOrder::Asc, None)).await;
+        assert_eq!(titles(&first), ["a", "b"]);
+        assert_eq!(first.total, 5);
+
+        let second = page(&repository, query(2, SortOrder::Asc, first.next_cursor)).await;
+        assert_eq!(titles(&second), ["c", "d"]);
+        assert_eq!(second.total, 5);
+
+        let third = page(&repository, query(2, SortOrder::Asc, second.next_cursor)).await;
+        assert_eq!(titles(&third), ["e"]);
+        assert!(third.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn cursor_follows_descending_order() {
+        let repository = repository(&["a", "b", "c"]).await;
+
+        let first = page(&repository, query(2, SortOrder::Desc, None)).await;
+        assert_eq!(titles(&first), ["c", "b"]);
+
+        let second = page(&repository, query(2, SortOrder::Desc, first.next_cursor)).await;
+        assert_eq!(titles(&second), ["a"]);
+        assert!(second.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn full_last_page_has_no_cursor() {
+        let repository = repository(&["a", "b"]).await;
+
+        let page = page(&repository, query(2, SortOrder::Asc, None)).await;
+
+        assert_eq!(titles(&page), ["a", "b"]);
+        assert!(page.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn cursor_of_another_order_is_rejected() {
+        let repository = repository(&["a", "b", "c"]).await;
+
+        let first = page(&repository, query(1, SortOrder::Asc, None)).await;
+
+        assert!(query(1, SortOrder::Desc, first.next_cursor).filter(CREATOR_ID).is_err());
+    }
+}
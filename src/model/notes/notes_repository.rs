@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+
+use crate::error::StorageResult;
+use crate::model::notes::notes_models::{NewNote, Note};
+use crate::model::notes::notes_query::{NotesFilter, NotesSlice};
+
+#[async_trait]
+pub trait NotesRepository: Send + Sync {
+    async fn insert_note(&self, new_note: NewNote) -> StorageResult<Note>;
+
+    async fn note_by_id(&self, note_id: u64) -> StorageResult<Option<Note>>;
+
+    async fn notes_by_creator(&self, creator_id: u32) -> StorageResult<Vec<Note>>;
+
+    /// Notes matching the filter in its order, starting after its cursor.
+    async fn notes_by_filter(&self, filter: &NotesFilter) -> StorageResult<NotesSlice>;
+
+    async fn all_notes(&self) -> StorageResult<Vec<Note>>;
+
+    /// Stores the note only if its version directly follows the stored one,
+    /// fails with `StorageError::Conflict` otherwise.
+    async fn update_note(&self, note: Note) -> StorageResult<Option<Note>>;
+
+    async fn delete_note(&self, note_id: u64) -> StorageResult<Option<Note>>;
+}
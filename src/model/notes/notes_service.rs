@@ -1,16 +1,84 @@
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
 
-use crate::error::{Error, NoteError, Result};
-use crate::model::notes::notes_models::{Note, NoteCreate, NoteEdit};
+use chrono::{Duration, Utc};
+
+use crate::context::AuthTokenContext;
+use crate::error::{Error, NoteError, NotebookError, Result, StorageError};
+use crate::log::log_layer;
+use crate::model::links::links_repository::LinksRepository;
+use crate::model::notebooks::notebooks_repository::NotebooksRepository;
+use crate::model::notes::notes_models::{NewNote, Note, NoteCreate, NoteEdit};
+use crate::model::notes::notes_query::{
+    invalid_field,
+    NoteSearchResult,
+    NotesFilter,
+    NotesPage,
+    NotesQuery,
+    NotesSearchQuery,
+};
+use crate::model::notes::notes_repository::NotesRepository;
+use crate::model::notes::notes_tags::{
+    normalize_tag,
+    normalize_tags,
+    TagCount,
+    TagMerge,
+    TagRename,
+};
+use crate::model::policy::{Action, Policy};
+use crate::model::revisions::revisions_models::{
+    NoteRevision,
+    NoteRevisionSummary,
+    RevisionsDiff,
+    RevisionsDiffQuery,
+};
+use crate::model::revisions::revisions_service::RevisionsService;
+use crate::model::search::SearchIndex;
+use crate::model::shares::shares_models::{
+    NoteShare,
+    NoteShareCreate,
+    NoteShareDetails,
+    ShareEvent,
+    SharedNote,
+};
+use crate::model::shares::shares_service::SharesService;
+use crate::settings::Trash;
+
+const NOTES_LAYER: &str = "NOTES";
+const TRASH_LAYER: &str = "TRASH";
+
+/// Times a tag replacement re-reads a note edited concurrently
+/// before giving up on it.
+const TAG_REPLACE_ATTEMPTS: usize = 3;
 
 #[derive(Clone)]
 pub struct NotesService {
-    notes_collection: Arc<Mutex<Vec<Option<Note>>>>,
+    repository: Arc<dyn NotesRepository>,
+    notebooks: Arc<dyn NotebooksRepository>,
+    revisions: RevisionsService,
+    shares: SharesService,
+    links: Arc<dyn LinksRepository>,
+    search: Arc<RwLock<SearchIndex>>,
 }
 
 impl NotesService {
-    pub fn new() -> Self {
-        Self { notes_collection: Arc::default() }
+    /// Builds the search index from every stored note.
+    pub async fn new(
+        repository: Arc<dyn NotesRepository>,
+        notebooks: Arc<dyn NotebooksRepository>,
+        revisions: RevisionsService,
+        shares: SharesService,
+        links: Arc<dyn LinksRepository>,
+    ) -> Result<Self> {
+        let notes = repository.all_notes().await
+            .map_err(|_| Error::Notes(NoteError::SearchFail))?
+            .into_iter()
+            .filter(|note| note.deleted_at.is_none())
+            .collect::<Vec<_>>();
+
+        let search = Arc::new(RwLock::new(SearchIndex::new(&notes)));
+
+        Ok(Self { repository, notebooks, revisions, shares, links, search })
     }
 }
 
@@ -18,92 +86,952 @@ impl NotesService {
     pub async fn create_note(
         &self, note_create: NoteCreate, creator_id: u32,
     ) -> Result<Note> {
-        let mut collection = self.notes_collection.lock()
-            .map_err(|_| Error::Notes(NoteError::CreateFail))?;
-
-        let id = collection.len() as u64;
+        self.check_notebook(note_create.notebook_id, creator_id).await?;
 
-        let note = Note {
-            id,
+        let new_note = NewNote {
             creator_id,
+            notebook_id: note_create.notebook_id,
             title: note_create.title,
             body: note_create.body,
+            created_at: Utc::now(),
+            tags: normalize_tags(note_create.tags)?,
         };
 
-        collection.push(Some(note.clone()));
+        let note = self.repository.insert_note(new_note).await
+            .map_err(|_| Error::Notes(NoteError::CreateFail))?;
+
+        self.index_note(&note);
 
         Ok(note)
     }
 
-    pub async fn list_of_notes(&self, creator_id: u32) -> Result<Vec<Note>> {
-        let collection = self.notes_collection.lock()
-            .map_err(|_| Error::Notes(NoteError::ReceiveFail))?;
+    pub async fn get_note(
+        &self, note_id: u64, reader: &AuthTokenContext,
+    ) -> Result<Note> {
+        let note = self.repository.note_by_id(note_id).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))?
+            .filter(|note| note.deleted_at.is_none())
+            .ok_or(Error::Notes(NoteError::NoteDoesNotExists))?;
 
-        let notes = collection.iter().filter_map(
-            |note_option| note_option.as_ref().and_then(
-                |note| if note.creator_id == creator_id {
-                    Some(note.clone())
-                } else {
-                    None
-                }
-            )
-        ).collect::<Vec<Note>>();
+        self.authorize_shared(
+            reader,
+            Action::Read,
+            &note,
+            Error::Notes(NoteError::ReaderCanNotReadNote),
+        ).await?;
+
+        Ok(note)
+    }
+
+    pub async fn list_of_notes(&self, reader: &AuthTokenContext) -> Result<Vec<Note>> {
+        let notes = self.repository.notes_by_creator(reader.user_id()).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))?
+            .into_iter()
+            .filter(|note| note.deleted_at.is_none())
+            .filter(|note| Policy::can(reader, Action::Read, note))
+            .collect();
 
         Ok(notes)
     }
 
+    pub async fn page_of_notes(
+        &self, reader: &AuthTokenContext, query: NotesQuery,
+    ) -> Result<NotesPage> {
+        let filter = query.filter(reader.user_id())?;
+
+        self.page(filter).await
+    }
+
+    /// Page of the owner's notes which are in one of the notebooks.
+    pub async fn page_of_notes_in(
+        &self, owner: &AuthTokenContext, notebook_ids: Vec<u64>, query: NotesQuery,
+    ) -> Result<NotesPage> {
+        let filter = NotesFilter {
+            notebook_ids: Some(notebook_ids),
+            ..query.filter(owner.user_id())?
+        };
+
+        self.page(filter).await
+    }
+
+    async fn page(&self, filter: NotesFilter) -> Result<NotesPage> {
+        let notes = self.repository.notes_by_filter(&filter).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))?;
+
+        Ok(filter.page(notes))
+    }
+
+    /// Ranked notes matching the query which the reader is allowed to read.
+    /// Only the reader's own and shared notes are searched, administrators
+    /// search every note.
+    pub async fn search_notes(
+        &self, reader: &AuthTokenContext, query: NotesSearchQuery,
+    ) -> Result<Vec<NoteSearchResult>> {
+        let limit = query.limit()?;
+
+        let mut readable_notes = if reader.is_admin() {
+            None
+        } else {
+            Some(self.readable_notes(reader).await?)
+        };
+
+        let scope = readable_notes.as_ref()
+            .map(|notes| notes.keys().copied().collect::<HashSet<_>>());
+
+        let (clauses, hits) = {
+            let search = self.search.read()
+                .map_err(|_| Error::Notes(NoteError::SearchFail))?;
+
+            let clauses = search.parse(&query.q);
+            let hits = search.search(&clauses, scope.as_ref());
+
+            (clauses, hits)
+        };
+
+        if clauses.is_empty() {
+            return Err(invalid_field("q", "Query must contain at least one word"));
+        }
+
+        let mut notes = Vec::new();
+
+        for hit in hits.into_iter().take(limit) {
+            let note = match readable_notes.as_mut() {
+                Some(readable_notes) => readable_notes.remove(&hit.note_id),
+                None => self.repository.note_by_id(hit.note_id).await
+                    .map_err(|_| Error::Notes(NoteError::SearchFail))?
+                    .filter(|note| note.deleted_at.is_none()),
+            };
+
+            if let Some(note) = note {
+                notes.push((note, hit.score));
+            }
+        }
+
+        let search = self.search.read()
+            .map_err(|_| Error::Notes(NoteError::SearchFail))?;
+
+        let results = notes.into_iter()
+            .map(|(note, score)| NoteSearchResult {
+                snippet: search.snippet(&note.body, &clauses),
+                note,
+                score,
+            })
+            .collect();
+
+        Ok(results)
+    }
+
     pub async fn edit_note(
-        &self, note_edit: NoteEdit, editor_id: u32,
+        &self, note_id: u64, note_edit: NoteEdit, editor: &AuthTokenContext,
     ) -> Result<Note> {
-        let mut collection = self.notes_collection.lock()
-            .map_err(|_| Error::Notes(NoteError::EditFail))?;
-
-        let note = collection.get_mut(note_edit.id as usize)
-            .user_can_change_note(editor_id, Error::Notes(NoteError::EditorCanNotEditNote))?
-            .and_then(|note| note.take())
+        let note = self.repository.note_by_id(note_id).await
+            .map_err(|_| Error::Notes(NoteError::EditFail))?
+            .filter(|note| note.deleted_at.is_none())
             .ok_or(Error::Notes(NoteError::NoteDoesNotExists))?;
 
+        self.authorize_shared(
+            editor,
+            Action::Edit,
+            &note,
+            Error::Notes(NoteError::EditorCanNotEditNote),
+        ).await?;
+
+        if note_edit.expected_version.is_some_and(|version| version != note.version) {
+            return Err(Error::Notes(NoteError::VersionMismatch(note.version)));
+        }
+
+        let tags = match note_edit.tags {
+            Some(tags) => normalize_tags(tags)?,
+            None => note.tags.clone(),
+        };
+
+        let notebook_id = note_edit.notebook_id.unwrap_or(note.notebook_id);
+
+        // Checked before the notebook, so writers can not probe the owner's notebooks
+        if notebook_id != note.notebook_id {
+            Policy::authorize(
+                editor,
+                Action::Move,
+                &note,
+                Error::Notes(NoteError::EditorCanNotMoveNote),
+            )?;
+        }
+
+        self.check_notebook(notebook_id, note.creator_id).await?;
+
+        let edited_at = Utc::now();
+
+        let is_content_changed = note_edit.title.as_ref()
+            .is_some_and(|title| *title != note.title)
+            || note_edit.body.as_ref().is_some_and(|body| *body != note.body);
+
+        let previous_note = is_content_changed.then(|| note.clone());
+
         let edited_note = Note {
             title: note_edit.title.unwrap_or(note.title),
             body: note_edit.body.unwrap_or(note.body),
+            tags,
+            notebook_id,
+            updated_at: edited_at,
+            last_editor_id: editor.user_id(),
+            version: note.version + 1,
+            ..note
+        };
+
+        let note = match self.repository.update_note(edited_note).await {
+            Ok(note) => note.ok_or(Error::Notes(NoteError::NoteDoesNotExists))?,
+            Err(StorageError::Conflict) => return Err(self.version_mismatch(note_id).await),
+            Err(_) => return Err(Error::Notes(NoteError::EditFail)),
+        };
+
+        // Recorded only once the edit is in, so a rejected edit leaves no revision
+        // behind. A failed recording is not a reason to report the edit as failed.
+        if let Some(previous_note) = previous_note {
+            let recorded = self.revisions
+                .record(&previous_note, editor.user_id(), edited_at)
+                .await;
+
+            if recorded.is_err() {
+                log_layer(NOTES_LAYER, format!("revision of note {note_id} not recorded").as_str());
+            }
+        }
+
+        self.index_note(&note);
+
+        Ok(note)
+    }
+
+    /// Moves the note into the trash, where it stays restorable
+    /// until it is purged.
+    pub async fn delete_note(
+        &self, note_id: u64, deleter: &AuthTokenContext,
+    ) -> Result<Note> {
+        let note = self.repository.note_by_id(note_id).await
+            .map_err(|_| Error::Notes(NoteError::DeleteFail))?
+            .filter(|note| note.deleted_at.is_none())
+            .ok_or(Error::Notes(NoteError::NoteDoesNotExists))?;
+
+        Policy::authorize(
+            deleter,
+            Action::Delete,
+            &note,
+            Error::Notes(NoteError::DeleterCanNotDeleteNote),
+        )?;
+
+        let notebook_id = note.notebook_id;
+
+        self.trash(note, notebook_id).await
+    }
+
+    /// Trashed notes of the owner, most recently deleted first.
+    pub async fn trash_of_notes(&self, owner: &AuthTokenContext) -> Result<Vec<Note>> {
+        let mut notes = self.repository.notes_by_creator(owner.user_id()).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))?
+            .into_iter()
+            .filter(|note| note.deleted_at.is_some())
+            .collect::<Vec<_>>();
+
+        notes.sort_by(|first, second| second.deleted_at.cmp(&first.deleted_at)
+            .then(first.id.cmp(&second.id))
+        );
+
+        Ok(notes)
+    }
+
+    /// Takes the note out of the trash. Notes whose notebook was deleted
+    /// in the meantime are restored at the root.
+    pub async fn restore_note(
+        &self, note_id: u64, restorer: &AuthTokenContext,
+    ) -> Result<Note> {
+        let note = self.trashed_note(note_id, restorer).await?;
+
+        let notebook_id = match note.notebook_id {
+            Some(notebook_id) => self.notebooks.notebook_by_id(notebook_id).await
+                .map_err(|_| Error::Notebooks(NotebookError::ReceiveFail))?
+                .map(|notebook| notebook.id),
+            None => None,
+        };
+
+        let restored_note = Note {
+            deleted_at: None,
+            notebook_id,
+            version: note.version + 1,
             ..note
         };
 
-        collection[edited_note.id as usize] = Some(edited_note.clone());
+        let note = self.repository.update_note(restored_note).await
+            .map_err(|_| Error::Notes(NoteError::EditFail))?
+            .ok_or(Error::Notes(NoteError::NoteDoesNotExists))?;
+
+        self.index_note(&note);
+
+        Ok(note)
+    }
 
-        Ok(edited_note)
+    /// Permanently deletes a trashed note with its revisions,
+    /// shares and public links.
+    pub async fn purge_note(
+        &self, note_id: u64, deleter: &AuthTokenContext,
+    ) -> Result<Note> {
+        self.trashed_note(note_id, deleter).await?;
+
+        self.purge(note_id, deleter.user_id()).await
     }
 
-    pub async fn delete_note(&self, note_id: u64, deleter_id: u32) -> Result<Note> {
-        let mut collection = self.notes_collection.lock()
+    /// Permanently deletes notes which have been in the trash
+    /// for longer than the retention period.
+    pub async fn purge_trash(&self, retention: Duration) -> Result<usize> {
+        let expired_before = Utc::now() - retention;
+
+        let expired_notes = self.repository.all_notes().await
+            .map_err(|_| Error::Notes(NoteError::DeleteFail))?
+            .into_iter()
+            .filter(|note| note.deleted_at
+                .is_some_and(|deleted_at| deleted_at < expired_before)
+            )
+            .collect::<Vec<_>>();
+
+        for note in expired_notes.iter() {
+            self.purge(note.id, note.creator_id).await?;
+        }
+
+        Ok(expired_notes.len())
+    }
+
+    /// Permanently deletes every note of a deleted user, trashed ones
+    /// included, and revokes the notes shared with them.
+    pub async fn purge_notes_of_user(&self, user_id: u32) -> Result<()> {
+        let notes = self.repository.notes_by_creator(user_id).await
             .map_err(|_| Error::Notes(NoteError::DeleteFail))?;
 
-        let deleted_note = collection.get_mut(note_id as usize)
-            .user_can_change_note(deleter_id, Error::Notes(NoteError::DeleterCanNotDeleteNote))?
-            .and_then(|note| note.take())
+        for note in notes {
+            self.purge(note.id, user_id).await?;
+        }
+
+        for share in self.shares.shared_with(user_id).await? {
+            self.shares.revoke(share.note_id, share.id, user_id).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn trashed_note(&self, note_id: u64, deleter: &AuthTokenContext) -> Result<Note> {
+        let note = self.repository.note_by_id(note_id).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))?
+            .filter(|note| note.deleted_at.is_some())
+            .ok_or(Error::Notes(NoteError::NoteDoesNotExists))?;
+
+        Policy::authorize(
+            deleter,
+            Action::Delete,
+            &note,
+            Error::Notes(NoteError::DeleterCanNotDeleteNote),
+        )?;
+
+        Ok(note)
+    }
+
+    /// Keeps the deletion time of notes which are already in the trash.
+    async fn trash(&self, note: Note, notebook_id: Option<u64>) -> Result<Note> {
+        let trashed_note = Note {
+            deleted_at: note.deleted_at.or(Some(Utc::now())),
+            notebook_id,
+            version: note.version + 1,
+            ..note
+        };
+
+        let note = self.repository.update_note(trashed_note).await
+            .map_err(|_| Error::Notes(NoteError::DeleteFail))?
+            .ok_or(Error::Notes(NoteError::NoteDoesNotExists))?;
+
+        if let Ok(mut search) = self.search.write() {
+            search.remove_note(note.id);
+        }
+
+        Ok(note)
+    }
+
+    async fn purge(&self, note_id: u64, actor_id: u32) -> Result<Note> {
+        self.revisions.delete_revisions(note_id).await?;
+        self.shares.revoke_all(note_id, actor_id).await?;
+        self.delete_links(note_id).await?;
+
+        let note = self.repository.delete_note(note_id).await
+            .map_err(|_| Error::Notes(NoteError::DeleteFail))?
             .ok_or(Error::Notes(NoteError::NoteDoesNotExists))?;
 
-        Ok(deleted_note)
+        if let Ok(mut search) = self.search.write() {
+            search.remove_note(note.id);
+        }
+
+        Ok(note)
+    }
+
+    async fn delete_links(&self, note_id: u64) -> Result<()> {
+        let links = self.links.links_by_note(note_id).await
+            .map_err(|_| Error::Notes(NoteError::DeleteFail))?;
+
+        for link in links {
+            self.links.delete_link(link.id).await
+                .map_err(|_| Error::Notes(NoteError::DeleteFail))?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn revisions_of_note(
+        &self, note_id: u64, reader: &AuthTokenContext,
+    ) -> Result<Vec<NoteRevisionSummary>> {
+        self.get_note(note_id, reader).await?;
+
+        let revisions = self.revisions.revisions(note_id).await?
+            .into_iter()
+            .rev()
+            .map(NoteRevisionSummary::from)
+            .collect();
+
+        Ok(revisions)
+    }
+
+    pub async fn revision_of_note(
+        &self, note_id: u64, revision_id: u64, reader: &AuthTokenContext,
+    ) -> Result<NoteRevision> {
+        self.get_note(note_id, reader).await?;
+
+        self.revisions.revision(note_id, revision_id).await
+    }
+
+    pub async fn diff_of_note(
+        &self, note_id: u64, query: RevisionsDiffQuery, reader: &AuthTokenContext,
+    ) -> Result<RevisionsDiff> {
+        let note = self.get_note(note_id, reader).await?;
+
+        self.revisions.diff(&note, query).await
+    }
+
+    /// Brings back the content of the revision as a new edit,
+    /// so the replaced content becomes a revision itself.
+    pub async fn restore_revision(
+        &self, note_id: u64, revision_id: u64, editor: &AuthTokenContext,
+    ) -> Result<Note> {
+        let revision = self.revision_of_note(note_id, revision_id, editor).await?;
+
+        let note_edit = NoteEdit {
+            title: Some(revision.title),
+            body: Some(revision.body),
+            tags: None,
+            notebook_id: None,
+            expected_version: None,
+        };
+
+        self.edit_note(note_id, note_edit, editor).await
+    }
+
+    /// Shares the note with another user, who then sees it
+    /// among the notes shared with them.
+    pub async fn share_note(
+        &self, note_id: u64, share_create: NoteShareCreate, sharer: &AuthTokenContext,
+    ) -> Result<NoteShareDetails> {
+        let note = self.shareable_note(note_id, sharer).await?;
+
+        self.shares.share(&note, sharer.user_id(), share_create).await
+    }
+
+    pub async fn shares_of_note(
+        &self, note_id: u64, sharer: &AuthTokenContext,
+    ) -> Result<Vec<NoteShareDetails>> {
+        let note = self.shareable_note(note_id, sharer).await?;
+
+        self.shares.shares_details(note.id).await
+    }
+
+    pub async fn revoke_share(
+        &self, note_id: u64, share_id: u64, sharer: &AuthTokenContext,
+    ) -> Result<NoteShare> {
+        let note = self.shareable_note(note_id, sharer).await?;
+
+        self.shares.revoke(note.id, share_id, sharer.user_id()).await
+    }
+
+    pub async fn share_events_of_note(
+        &self, note_id: u64, sharer: &AuthTokenContext,
+    ) -> Result<Vec<ShareEvent>> {
+        let note = self.shareable_note(note_id, sharer).await?;
+
+        self.shares.events(note.id).await
+    }
+
+    /// Notes of other users shared with the reader, except trashed ones.
+    pub async fn shared_notes(&self, reader: &AuthTokenContext) -> Result<Vec<SharedNote>> {
+        let mut shared_notes = Vec::new();
+
+        for share in self.shares.shared_with(reader.user_id()).await? {
+            let note = self.repository.note_by_id(share.note_id).await
+                .map_err(|_| Error::Notes(NoteError::ReceiveFail))?
+                .filter(|note| note.deleted_at.is_none());
+
+            if let Some(note) = note {
+                shared_notes.push(SharedNote { note, permission: share.permission });
+            }
+        }
+
+        Ok(shared_notes)
+    }
+
+    /// Active notes of the reader and those shared with them, by id.
+    async fn readable_notes(&self, reader: &AuthTokenContext) -> Result<HashMap<u64, Note>> {
+        let own_notes = self.list_of_notes(reader).await?;
+        let shared_notes = self.shared_notes(reader).await?
+            .into_iter()
+            .map(|shared_note| shared_note.note);
+
+        let notes = own_notes.into_iter()
+            .chain(shared_notes)
+            .map(|note| (note.id, note))
+            .collect();
+
+        Ok(notes)
+    }
+
+    /// Active note the sharer is allowed to share, to others or publicly.
+    pub async fn shareable_note(&self, note_id: u64, sharer: &AuthTokenContext) -> Result<Note> {
+        let note = self.get_note(note_id, sharer).await?;
+
+        Policy::authorize(
+            sharer,
+            Action::Share,
+            &note,
+            Error::Notes(NoteError::SharerCanNotShareNote),
+        )?;
+
+        Ok(note)
+    }
+
+    /// Active note behind a public link. Links are checked by `LinksService`.
+    pub async fn linked_note(&self, note_id: u64) -> Result<Note> {
+        self.repository.note_by_id(note_id).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))?
+            .filter(|note| note.deleted_at.is_none())
+            .ok_or(Error::Notes(NoteError::NoteDoesNotExists))
+    }
+
+    /// Checks the action against the permission the note is shared
+    /// with the actor, looked up only for notes of other users.
+    async fn authorize_shared(
+        &self, actor: &AuthTokenContext, action: Action, note: &Note, error: Error,
+    ) -> Result<()> {
+        if Policy::can(actor, action, note) {
+            return Ok(());
+        }
+
+        let permission = self.shares.permission(note.id, actor.user_id()).await?;
+
+        Policy::authorize_shared(actor, action, note, permission, error)
+    }
+
+    /// Moves the owner's notes out of the given notebooks, keeping
+    /// their update time as the content stays the same.
+    pub async fn rehome_notes(
+        &self, owner: &AuthTokenContext, notebook_ids: &[u64], target: Option<u64>,
+    ) -> Result<()> {
+        for note in self.notes_in(owner, notebook_ids).await? {
+            let version = note.version + 1;
+
+            self.repository.update_note(Note { notebook_id: target, version, ..note }).await
+                .map_err(|_| Error::Notes(NoteError::EditFail))?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the owner's notes of the given notebooks into the trash,
+    /// detached from the notebooks which are about to be deleted.
+    pub async fn trash_notes_in(
+        &self, owner: &AuthTokenContext, notebook_ids: &[u64],
+    ) -> Result<()> {
+        for note in self.notes_in(owner, notebook_ids).await? {
+            self.trash(note, None).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn notes_in(
+        &self, owner: &AuthTokenContext, notebook_ids: &[u64],
+    ) -> Result<Vec<Note>> {
+        let notes = self.repository.notes_by_creator(owner.user_id()).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))?
+            .into_iter()
+            .filter(|note| note.notebook_id
+                .is_some_and(|notebook_id| notebook_ids.contains(&notebook_id))
+            )
+            .collect();
+
+        Ok(notes)
+    }
+
+    /// Error of an edit which lost the race against another one.
+    async fn version_mismatch(&self, note_id: u64) -> Error {
+        match self.repository.note_by_id(note_id).await {
+            Ok(Some(note)) => Error::Notes(NoteError::VersionMismatch(note.version)),
+            Ok(None) => Error::Notes(NoteError::NoteDoesNotExists),
+            Err(_) => Error::Notes(NoteError::EditFail),
+        }
+    }
+
+    /// Notes can only be put into notebooks of their creator.
+    async fn check_notebook(&self, notebook_id: Option<u64>, creator_id: u32) -> Result<()> {
+        let Some(notebook_id) = notebook_id else {
+            return Ok(());
+        };
+
+        self.notebooks.notebook_by_id(notebook_id).await
+            .map_err(|_| Error::Notebooks(NotebookError::ReceiveFail))?
+            .filter(|notebook| notebook.owner_id == creator_id)
+            .map(|_| ())
+            .ok_or(Error::Notebooks(NotebookError::NotebookDoesNotExists))
+    }
+
+    /// Tags of the owner's notes with the number of notes having them.
+    /// Tags of notes in the trash are not counted.
+    pub async fn tags(&self, owner: &AuthTokenContext) -> Result<Vec<TagCount>> {
+        let notes = self.list_of_notes(owner).await?;
+
+        Ok(count_tags(&notes))
+    }
+
+    /// Renames the tag on all the owner's notes. Notes in the trash are
+    /// renamed too, so they do not bring the old name back once restored.
+    pub async fn rename_tag(
+        &self, owner: &AuthTokenContext, tag: &str, tag_rename: TagRename,
+    ) -> Result<TagCount> {
+        let (from, to) = (normalize_tag(tag)?, normalize_tag(&tag_rename.name)?);
+        let notes = self.notes_with_trash(owner).await?;
+        let tags = count_tags(&notes);
+
+        if !tags.iter().any(|tag| tag.name == from) {
+            return Err(Error::Notes(NoteError::TagDoesNotExists));
+        }
+
+        if from != to && tags.iter().any(|tag| tag.name == to) {
+            return Err(Error::Notes(NoteError::TagAlreadyExists));
+        }
+
+        self.replace_tag(owner, notes, &from, &to).await
+    }
+
+    /// Replaces the tag with another existing one on all the owner's notes,
+    /// the ones in the trash included.
+    pub async fn merge_tag(
+        &self, owner: &AuthTokenContext, tag: &str, tag_merge: TagMerge,
+    ) -> Result<TagCount> {
+        let (from, into) = (normalize_tag(tag)?, normalize_tag(&tag_merge.into)?);
+
+        if from == into {
+            return Err(invalid_field("into", "Tag can not be merged into itself"));
+        }
+
+        let notes = self.notes_with_trash(owner).await?;
+        let tags = count_tags(&notes);
+
+        if !tags.iter().any(|tag| tag.name == from)
+            || !tags.iter().any(|tag| tag.name == into)
+        {
+            return Err(Error::Notes(NoteError::TagDoesNotExists));
+        }
+
+        self.replace_tag(owner, notes, &from, &into).await
+    }
+
+    /// Notes of the owner a tag is renamed or merged on, trashed ones included.
+    async fn notes_with_trash(&self, owner: &AuthTokenContext) -> Result<Vec<Note>> {
+        self.repository.notes_by_creator(owner.user_id()).await
+            .map_err(|_| Error::Notes(NoteError::EditFail))
+    }
+
+    /// Replaces the tag on the given notes, returning the count of the new
+    /// tag among the notes out of the trash.
+    async fn replace_tag(
+        &self, owner: &AuthTokenContext, notes: Vec<Note>, from: &str, to: &str,
+    ) -> Result<TagCount> {
+        for note in notes {
+            self.replace_tag_of_note(note, from, to).await?;
+        }
+
+        let count = self.tags(owner).await?
+            .into_iter()
+            .find(|tag| tag.name == to)
+            .unwrap_or(TagCount { name: to.to_string(), notes: 0 });
+
+        Ok(count)
+    }
+
+    /// Replaces the tag on a single note. An edit racing with the
+    /// replacement makes it re-read the note and try again, a note
+    /// which keeps changing is reported as a version mismatch.
+    async fn replace_tag_of_note(&self, mut note: Note, from: &str, to: &str) -> Result<()> {
+        for _ in 0..TAG_REPLACE_ATTEMPTS {
+            if !note.tags.iter().any(|tag| tag == from) {
+                return Ok(());
+            }
+
+            let mut tags = note.tags.iter()
+                .filter(|tag| *tag != from)
+                .cloned()
+                .chain([to.to_string()])
+                .collect::<Vec<_>>();
+
+            tags.sort();
+            tags.dedup();
+
+            let version = note.version + 1;
+
+            match self.repository.update_note(Note { tags, version, ..note.clone() }).await {
+                Ok(_) => return Ok(()),
+                Err(StorageError::Conflict) => {}
+                Err(_) => return Err(Error::Notes(NoteError::EditFail)),
+            }
+
+            note = match self.repository.note_by_id(note.id).await {
+                Ok(Some(note)) => note,
+                Ok(None) => return Ok(()),
+                Err(_) => return Err(Error::Notes(NoteError::EditFail)),
+            };
+        }
+
+        Err(self.version_mismatch(note.id).await)
+    }
+
+    /// The note is already stored at this point, so a poisoned index
+    /// must not turn a successful write into an error.
+    fn index_note(&self, note: &Note) {
+        if let Ok(mut search) = self.search.write() {
+            search.index_note(note);
+        }
     }
 }
 
-trait NoteChanger: Sized {
-    fn user_can_change_note(self, changer_id: u32, error: Error) -> Result<Self>;
+fn count_tags(notes: &[Note]) -> Vec<TagCount> {
+    let mut counts = BTreeMap::<&str, usize>::new();
+
+    for tag in notes.iter().flat_map(|note| note.tags.iter()) {
+        *counts.entry(tag).or_default() += 1;
+    }
+
+    counts.into_iter()
+        .map(|(name, notes)| TagCount { name: name.to_string(), notes })
+        .collect()
 }
 
-impl NoteChanger for Option<&mut Option<Note>> {
-    fn user_can_change_note(self, changer_id: u32, error: Error) -> Result<Self> {
-        match self.as_ref() {
-            None => Err(error),
-            Some(note) => {
-                let is_user_can_change = note.as_ref()
-                    .is_some_and(move |note| note.creator_id != changer_id);
-                if is_user_can_change {
-                    Ok(self)
-                } else {
-                    Err(error)
+pub async fn purge_trash_periodically(notes: NotesService, trash: Trash) {
+    let retention = Duration::days(trash.retention_days as i64);
+    let mut interval = tokio::time::interval(
+        std::time::Duration::from_secs(trash.purge_interval_seconds),
+    );
+
+    loop {
+        interval.tick().await;
+
+        match notes.purge_trash(retention).await {
+            Ok(0) => {}
+            Ok(purged) => log_layer(TRASH_LAYER, format!("purged {purged} notes").as_str()),
+            Err(error) => log_layer(TRASH_LAYER, format!("purge failed: {error}").as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::error::StorageResult;
+    use crate::model::notes::notes_query::{NotesFilter, NotesSlice};
+    use crate::model::notes::notes_tags::TagMerge;
+    use crate::model::storage::memory::{
+        InMemoryLinksRepository,
+        InMemoryNotebooksRepository,
+        InMemoryNotesRepository,
+        InMemoryRevisionsRepository,
+        InMemoryShareEventsRepository,
+        InMemorySharesRepository,
+        InMemoryUsersRepository,
+    };
+    use crate::model::users::users_models::UserRole;
+
+    const OWNER_ID: u32 = 1;
+
+    /// Notes repository in which another edit of the note gets in
+    /// ahead of each of the next `races` updates.
+    #[derive(Default)]
+    struct RacingNotesRepository {
+        notes: InMemoryNotesRepository,
+        races: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl NotesRepository for RacingNotesRepository {
+        async fn insert_note(&self, new_note: NewNote) -> StorageResult<Note> {
+            self.notes.insert_note(new_note).await
+        }
+
+        async fn note_by_id(&self, note_id: u64) -> StorageResult<Option<Note>> {
+            self.notes.note_by_id(note_id).await
+        }
+
+        async fn notes_by_creator(&self, creator_id: u32) -> StorageResult<Vec<Note>> {
+            self.notes.notes_by_creator(creator_id).await
+        }
+
+        async fn notes_by_filter(&self, filter: &NotesFilter) -> StorageResult<NotesSlice> {
+            self.notes.notes_by_filter(filter).await
+        }
+
+        async fn all_notes(&self) -> StorageResult<Vec<Note>> {
+            self.notes.all_notes().await
+        }
+
+        async fn update_note(&self, note: Note) -> StorageResult<Option<Note>> {
+            let is_raced = self.races
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |races| races.checked_sub(1))
+                .is_ok();
+
+            if is_raced {
+                if let Some(stored) = self.notes.note_by_id(note.id).await? {
+                    self.notes.update_note(Note {
+                        body: format!("{} raced", stored.body),
+                        version: stored.version + 1,
+                        ..stored
+                    }).await?;
                 }
             }
+
+            self.notes.update_note(note).await
         }
+
+        async fn delete_note(&self, note_id: u64) -> StorageResult<Option<Note>> {
+            self.notes.delete_note(note_id).await
+        }
+    }
+
+    struct Fixture {
+        service: NotesService,
+        repository: Arc<RacingNotesRepository>,
+        owner: AuthTokenContext,
+    }
+
+    async fn fixture() -> Fixture {
+        let repository = Arc::new(RacingNotesRepository::default());
+
+        let service = NotesService::new(
+            repository.clone(),
+            Arc::new(InMemoryNotebooksRepository::default()),
+            RevisionsService::new(Arc::new(InMemoryRevisionsRepository::default()), 10),
+            SharesService::new(
+                Arc::new(InMemorySharesRepository::default()),
+                Arc::new(InMemoryShareEventsRepository::default()),
+                Arc::new(InMemoryUsersRepository::default()),
+            ),
+            Arc::new(InMemoryLinksRepository::default()),
+        ).await.unwrap();
+
+        Fixture { service, repository, owner: AuthTokenContext::new(OWNER_ID, UserRole::User) }
+    }
+
+    impl Fixture {
+        async fn create(&self, tags: &[&str]) -> Note {
+            self.service.create_note(NoteCreate {
+                title: "Title".to_string(),
+                body: "Body".to_string(),
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                notebook_id: None,
+            }, OWNER_ID).await.unwrap()
+        }
+
+        async fn stored(&self, note: &Note) -> Note {
+            self.repository.note_by_id(note.id).await.unwrap().unwrap()
+        }
+
+        async fn rename(&self, from: &str, to: &str) -> Result<TagCount> {
+            self.service
+                .rename_tag(&self.owner, from, TagRename { name: to.to_string() })
+                .await
+        }
+    }
+
+    #[tokio::test]
+    async fn rename_covers_trashed_notes() {
+        let fixture = fixture().await;
+        let kept = fixture.create(&["old"]).await;
+        let trashed = fixture.create(&["old", "other"]).await;
+        fixture.service.delete_note(trashed.id, &fixture.owner).await.unwrap();
+
+        let count = fixture.rename("old", "new").await.unwrap();
+
+        assert_eq!((count.name.as_str(), count.notes), ("new", 1));
+        assert_eq!(fixture.stored(&kept).await.tags, ["new"]);
+        assert_eq!(fixture.stored(&trashed).await.tags, ["new", "other"]);
+    }
+
+    #[tokio::test]
+    async fn rename_onto_tag_of_trashed_note_is_rejected() {
+        let fixture = fixture().await;
+        let kept = fixture.create(&["old"]).await;
+        let trashed = fixture.create(&["new"]).await;
+        fixture.service.delete_note(trashed.id, &fixture.owner).await.unwrap();
+
+        let result = fixture.rename("old", "new").await;
+
+        assert!(matches!(result, Err(Error::Notes(NoteError::TagAlreadyExists))));
+        assert_eq!(fixture.stored(&kept).await.tags, ["old"]);
+    }
+
+    #[tokio::test]
+    async fn tag_of_trashed_notes_only_can_be_merged() {
+        let fixture = fixture().await;
+        fixture.create(&["kept"]).await;
+        let trashed = fixture.create(&["old"]).await;
+        fixture.service.delete_note(trashed.id, &fixture.owner).await.unwrap();
+
+        let count = fixture.service
+            .merge_tag(&fixture.owner, "old", TagMerge { into: "kept".to_string() })
+            .await
+            .unwrap();
+
+        assert_eq!(count.notes, 1);
+        assert_eq!(fixture.stored(&trashed).await.tags, ["kept"]);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn racing_edit_is_retried_and_kept() {
+        let fixture = fixture().await;
+        let first = fixture.create(&["old"]).await;
+        let second = fixture.create(&["old"]).await;
+        fixture.repository.races.store(1, Ordering::SeqCst);
+
+        fixture.rename("old", "new").await.unwrap();
+
+        let first = fixture.stored(&first).await;
+        assert_eq!(first.tags, ["new"]);
+        assert_eq!(first.body, "Body raced");
+        assert_eq!(fixture.stored(&second).await.tags, ["new"]);
+    }
+
+    #[tokio::test]
+    async fn note_which_keeps_changing_is_reported() {
+        let fixture = fixture().await;
+        let note = fixture.create(&["old"]).await;
+        fixture.repository.races.store(TAG_REPLACE_ATTEMPTS, Ordering::SeqCst);
+
+        let result = fixture.rename("old", "new").await;
+
+        let stored = fixture.stored(&note).await;
+        assert!(matches!(
+            result,
+            Err(Error::Notes(NoteError::VersionMismatch(version))) if version == stored.version
+        ));
+        assert_eq!(stored.tags, ["old"]);
+    }
+}
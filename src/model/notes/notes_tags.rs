@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::model::notes::notes_query::invalid_field;
+
+const MAX_TAGS: usize = 20;
+const MAX_TAG_LENGTH: usize = 64;
+
+/// Separator of tags in the `tag` filter of the notes list, so it can not
+/// be a part of a tag.
+pub const TAG_SEPARATOR: char = ',';
+
+#[derive(Serialize)]
+pub struct TagCount {
+    pub name: String,
+    pub notes: usize,
+}
+
+#[derive(Deserialize)]
+pub struct TagRename {
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct TagMerge {
+    /// Tag which replaces the merged one
+    pub into: String,
+}
+
+/// Normalizes every tag, then drops duplicates and sorts them.
+pub fn normalize_tags(tags: Vec<String>) -> Result<Vec<String>> {
+    let mut tags = tags.iter()
+        .map(|tag| normalize_tag(tag))
+        .collect::<Result<Vec<_>>>()?;
+
+    tags.sort();
+    tags.dedup();
+
+    if tags.len() > MAX_TAGS {
+        return Err(invalid_field("tags", "Note can not have more than 20 tags"));
+    }
+
+    Ok(tags)
+}
+
+/// Tags are trimmed and case insensitive.
+pub fn normalize_tag(tag: &str) -> Result<String> {
+    let tag = tag.trim().to_lowercase();
+
+    if tag.is_empty() || tag.chars().count() > MAX_TAG_LENGTH {
+        return Err(invalid_field("tags", "Tag must be 1 to 64 characters long"));
+    }
+
+    if tag.chars().any(|character| character == TAG_SEPARATOR || character.is_control()) {
+        return Err(invalid_field(
+            "tags",
+            "Tag can not contain commas or control characters",
+        ));
+    }
+
+    Ok(tag)
+}
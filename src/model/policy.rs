@@ -0,0 +1,161 @@
+use crate::context::AuthTokenContext;
+use crate::error::{Error, Result};
+use crate::model::notes::notes_models::Note;
+use crate::model::shares::shares_models::SharePermission;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Action {
+    Read,
+    Edit,
+    /// Putting the note into another notebook of its creator
+    Move,
+    Delete,
+    Share,
+}
+
+/// Single place deciding what an authenticated user may do with a note.
+/// Administrators may do anything, other users anything with their own
+/// notes and only what the note is shared with them for with the others.
+pub struct Policy;
+
+impl Policy {
+    pub fn can(actor: &AuthTokenContext, action: Action, note: &Note) -> bool {
+        Self::can_shared(actor, action, note, None)
+    }
+
+    /// `permission` is the one the note is shared with the actor.
+    pub fn can_shared(
+        actor: &AuthTokenContext,
+        action: Action,
+        note: &Note,
+        permission: Option<SharePermission>,
+    ) -> bool {
+        if actor.is_admin() || note.creator_id == actor.user_id() {
+            return true;
+        }
+
+        match action {
+            Action::Read => permission.is_some(),
+            Action::Edit => permission == Some(SharePermission::Write),
+            Action::Move
+            | Action::Delete
+            | Action::Share => false,
+        }
+    }
+
+    pub fn authorize(
+        actor: &AuthTokenContext, action: Action, note: &Note, error: Error,
+    ) -> Result<()> {
+        Self::authorize_shared(actor, action, note, None, error)
+    }
+
+    pub fn authorize_shared(
+        actor: &AuthTokenContext,
+        action: Action,
+        note: &Note,
+        permission: Option<SharePermission>,
+        error: Error,
+    ) -> Result<()> {
+        if Self::can_shared(actor, action, note, permission) {
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::error::UserError;
+    use crate::model::notes::notes_models::INITIAL_NOTE_VERSION;
+    use crate::model::users::users_models::UserRole;
+
+    const OWNER_ID: u32 = 1;
+    const OTHER_ID: u32 = 2;
+
+    fn note() -> Note {
+        Note {
+            id: 0,
+            creator_id: OWNER_ID,
+            title: "Title".to_string(),
+            body: "Body".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_editor_id: OWNER_ID,
+            tags: Vec::new(),
+            notebook_id: None,
+            version: INITIAL_NOTE_VERSION,
+            deleted_at: None,
+        }
+    }
+
+    fn user(user_id: u32) -> AuthTokenContext {
+        AuthTokenContext::new(user_id, UserRole::User)
+    }
+
+    fn admin() -> AuthTokenContext {
+        AuthTokenContext::new(OTHER_ID, UserRole::Admin)
+    }
+
+    /// Expected outcomes of read, edit, move, delete and share, in that order.
+    fn assert_allowed(
+        actor: &AuthTokenContext, permission: Option<SharePermission>, expected: [bool; 5],
+    ) {
+        let actions = [Action::Read, Action::Edit, Action::Move, Action::Delete, Action::Share];
+
+        for (action, expected) in actions.into_iter().zip(expected) {
+            assert_eq!(
+                Policy::can_shared(actor, action, &note(), permission),
+                expected,
+                "{action:?} with {permission:?}",
+            );
+        }
+    }
+
+    #[test]
+    fn owner_can_do_anything() {
+        assert_allowed(&user(OWNER_ID), None, [true; 5]);
+        assert!(Policy::can(&user(OWNER_ID), Action::Delete, &note()));
+    }
+
+    #[test]
+    fn admin_can_do_anything() {
+        assert_allowed(&admin(), None, [true; 5]);
+        assert!(Policy::can(&admin(), Action::Edit, &note()));
+    }
+
+    #[test]
+    fn stranger_can_do_nothing() {
+        assert_allowed(&user(OTHER_ID), None, [false; 5]);
+        assert!(!Policy::can(&user(OTHER_ID), Action::Read, &note()));
+    }
+
+    #[test]
+    fn shared_read_can_only_read() {
+        assert_allowed(&user(OTHER_ID), Some(SharePermission::Read), [true, false, false, false, false]);
+    }
+
+    #[test]
+    fn shared_write_can_read_and_edit_but_not_move() {
+        assert_allowed(
+            &user(OTHER_ID),
+            Some(SharePermission::Write),
+            [true, true, false, false, false],
+        );
+    }
+
+    #[test]
+    fn authorize_returns_given_error() {
+        let result = Policy::authorize(
+            &user(OTHER_ID),
+            Action::Delete,
+            &note(),
+            Error::User(UserError::AuthFail),
+        );
+
+        assert!(matches!(result, Err(Error::User(UserError::AuthFail))));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod revisions_diff;
+pub mod revisions_models;
+pub mod revisions_repository;
+pub mod revisions_service;
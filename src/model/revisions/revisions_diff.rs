@@ -0,0 +1,299 @@
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffOperation {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Serialize)]
+pub struct DiffLine {
+    pub operation: DiffOperation,
+    pub text: String,
+}
+
+/// Edit distance searched from either end of a differing part before it is
+/// shown as deleted and inserted whole. Bounds the time spent on bodies
+/// with little in common.
+const MAX_SEARCH_DISTANCE: isize = 1024;
+
+/// Shortest line-level edit script turning `old` into `new`, computed with
+/// the linear space variant of the Myers algorithm.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    diff_range(&old, &new, &mut lines);
+
+    lines
+}
+
+/// Splits the lines at the middle snake of their shortest edit script
+/// and diffs both halves the same way.
+fn diff_range(old: &[&str], new: &[&str], lines: &mut Vec<DiffLine>) {
+    let prefix = old.iter().zip(new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let (old_middle, new_middle) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    push_lines(lines, DiffOperation::Equal, &old[..prefix]);
+
+    match middle_snake(old_middle, new_middle) {
+        Some(snake) => {
+            diff_range(&old_middle[..snake.x], &new_middle[..snake.y], lines);
+            push_lines(lines, DiffOperation::Equal, &old_middle[snake.x..snake.u]);
+            diff_range(&old_middle[snake.u..], &new_middle[snake.v..], lines);
+        }
+        None => {
+            push_lines(lines, DiffOperation::Delete, old_middle);
+            push_lines(lines, DiffOperation::Insert, new_middle);
+        }
+    }
+
+    push_lines(lines, DiffOperation::Equal, &old[old.len() - suffix..]);
+}
+
+/// Equal lines from `(x, y)` to `(u, v)` in the middle of a shortest script.
+struct Snake {
+    x: usize,
+    y: usize,
+    u: usize,
+    v: usize,
+}
+
+/// Searches shortest paths from both ends until they overlap. Lines which
+/// have nothing in common or differ too much have no snake worth finding.
+fn middle_snake(old: &[&str], new: &[&str]) -> Option<Snake> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    if n == 0 || m == 0 {
+        return None;
+    }
+
+    let delta = n - m;
+    let is_odd = delta % 2 != 0;
+    let max = ((n + m + 1) / 2).min(MAX_SEARCH_DISTANCE);
+
+    // Furthest reaching x of every diagonal k = x - y, the backward one
+    // counted from the ends of the lines
+    let offset = max + 1;
+    let mut forward = vec![0isize; (2 * max + 3) as usize];
+    let mut backward = vec![0isize; (2 * max + 3) as usize];
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let (start, end) = extend(&mut forward, offset, d, k, n, m, |x, y| {
+                old[x] == new[y]
+            });
+
+            let backward_k = delta - k;
+            if is_odd
+                && (-(d - 1)..=d - 1).contains(&backward_k)
+                && end + backward[(offset + backward_k) as usize] >= n
+            {
+                return Some(Snake {
+                    x: start as usize,
+                    y: (start - k) as usize,
+                    u: end as usize,
+                    v: (end - k) as usize,
+                });
+            }
+        }
+
+        for k in (-d..=d).step_by(2) {
+            let (start, end) = extend(&mut backward, offset, d, k, n, m, |x, y| {
+                old[old.len() - 1 - x] == new[new.len() - 1 - y]
+            });
+
+            let forward_k = delta - k;
+            if !is_odd
+                && (-d..=d).contains(&forward_k)
+                && forward[(offset + forward_k) as usize] + end >= n
+            {
+                return Some(Snake {
+                    x: (n - end) as usize,
+                    y: (m - end + k) as usize,
+                    u: (n - start) as usize,
+                    v: (m - start + k) as usize,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// Furthest reaching path of round `d` on diagonal `k`, followed by the
+/// snake of equal lines after it. Returns where the snake starts and ends.
+fn extend(
+    furthest: &mut [isize],
+    offset: isize,
+    d: isize,
+    k: isize,
+    n: isize,
+    m: isize,
+    is_equal: impl Fn(usize, usize) -> bool,
+) -> (isize, isize) {
+    let at = |k: isize| furthest[(offset + k) as usize];
+
+    let start = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+        at(k + 1)
+    } else {
+        at(k - 1) + 1
+    };
+
+    let mut x = start;
+    while x < n && x - k < m && x - k >= 0 && is_equal(x as usize, (x - k) as usize) {
+        x += 1;
+    }
+
+    furthest[(offset + k) as usize] = x;
+
+    (start, x)
+}
+
+fn push_lines(lines: &mut Vec<DiffLine>, operation: DiffOperation, texts: &[&str]) {
+    lines.extend(texts.iter().map(|text| line(operation, text)));
+}
+
+fn line(operation: DiffOperation, text: &str) -> DiffLine {
+    DiffLine { operation, text: text.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DiffOperation::{Delete, Equal, Insert};
+
+    fn script(old: &str, new: &str) -> Vec<(DiffOperation, String)> {
+        diff_lines(old, new).into_iter()
+            .map(|line| (line.operation, line.text))
+            .collect()
+    }
+
+    fn expected(lines: &[(DiffOperation, &str)]) -> Vec<(DiffOperation, String)> {
+        lines.iter()
+            .map(|(operation, text)| (*operation, text.to_string()))
+            .collect()
+    }
+
+    /// Old and new lines put back together from the script.
+    fn reconstruct(lines: &[DiffLine]) -> (Vec<&str>, Vec<&str>) {
+        let old = lines.iter()
+            .filter(|line| line.operation != Insert)
+            .map(|line| line.text.as_str())
+            .collect();
+        let new = lines.iter()
+            .filter(|line| line.operation != Delete)
+            .map(|line| line.text.as_str())
+            .collect();
+
+        (old, new)
+    }
+
+    fn longest_common_subsequence(old: &[&str], new: &[&str]) -> usize {
+        let mut lengths = vec![vec![0; new.len() + 1]; old.len() + 1];
+
+        for i in 0..old.len() {
+            for j in 0..new.len() {
+                lengths[i + 1][j + 1] = if old[i] == new[j] {
+                    lengths[i][j] + 1
+                } else {
+                    lengths[i][j + 1].max(lengths[i + 1][j])
+                };
+            }
+        }
+
+        lengths[old.len()][new.len()]
+    }
+
+    #[test]
+    fn empty_inputs_have_empty_script() {
+        assert!(diff_lines("", "").is_empty());
+    }
+
+    #[test]
+    fn identical_inputs_are_equal() {
+        assert_eq!(
+            script("a\nb\nc", "a\nb\nc"),
+            expected(&[(Equal, "a"), (Equal, "b"), (Equal, "c")]),
+        );
+    }
+
+    #[test]
+    fn pure_insert() {
+        assert_eq!(script("", "a\nb"), expected(&[(Insert, "a"), (Insert, "b")]));
+        assert_eq!(
+            script("a\nc", "a\nb\nc"),
+            expected(&[(Equal, "a"), (Insert, "b"), (Equal, "c")]),
+        );
+    }
+
+    #[test]
+    fn pure_delete() {
+        assert_eq!(script("a\nb", ""), expected(&[(Delete, "a"), (Delete, "b")]));
+        assert_eq!(
+            script("a\nb\nc", "a\nc"),
+            expected(&[(Equal, "a"), (Delete, "b"), (Equal, "c")]),
+        );
+    }
+
+    #[test]
+    fn interleaved_edits() {
+        let lines = script("a\nb\nc\nd\ne", "a\nx\nc\ne\nf");
+
+        assert_eq!(lines.iter().filter(|(operation, _)| *operation == Equal).count(), 3);
+        assert_eq!(lines.iter().filter(|(operation, _)| *operation == Delete).count(), 2);
+        assert_eq!(lines.iter().filter(|(operation, _)| *operation == Insert).count(), 2);
+    }
+
+    #[test]
+    fn script_reconstructs_both_sides_and_is_shortest() {
+        // Small alphabet, so that the lines have a lot in common
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut random = move |bound: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % bound
+        };
+
+        for _ in 0..500 {
+            let old = (0..random(30)).map(|_| random(4).to_string()).collect::<Vec<_>>();
+            let new = (0..random(30)).map(|_| random(4).to_string()).collect::<Vec<_>>();
+            let (old, new) = (old.join("\n"), new.join("\n"));
+
+            let lines = diff_lines(&old, &new);
+            let (old_lines, new_lines) = (
+                old.lines().collect::<Vec<_>>(),
+                new.lines().collect::<Vec<_>>(),
+            );
+
+            assert_eq!(reconstruct(&lines), (old_lines.clone(), new_lines.clone()));
+
+            let edits = lines.iter().filter(|line| line.operation != Equal).count();
+            let common = longest_common_subsequence(&old_lines, &new_lines);
+            assert_eq!(edits, old_lines.len() + new_lines.len() - 2 * common);
+        }
+    }
+
+    #[test]
+    fn distinct_large_inputs_are_replaced_whole() {
+        let old = (0..5000).map(|line| format!("old {line}")).collect::<Vec<_>>().join("\n");
+        let new = (0..5000).map(|line| format!("new {line}")).collect::<Vec<_>>().join("\n");
+
+        let lines = diff_lines(&old, &new);
+
+        assert_eq!(lines.len(), 10000);
+        assert_eq!(reconstruct(&lines), (old.lines().collect(), new.lines().collect()));
+    }
+}
@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::revisions::revisions_diff::DiffLine;
+
+/// Content of a note as it was before an edit, with the author
+/// and the time of that edit. Revisions are never changed.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NoteRevision {
+    pub id: u64,
+    pub note_id: u64,
+    pub editor_id: u32,
+    pub edited_at: DateTime<Utc>,
+    pub title: String,
+    pub body: String,
+}
+
+pub struct NewNoteRevision {
+    pub note_id: u64,
+    pub editor_id: u32,
+    pub edited_at: DateTime<Utc>,
+    pub title: String,
+    pub body: String,
+}
+
+/// Revision without its body, as returned by the revisions list.
+#[derive(Serialize)]
+pub struct NoteRevisionSummary {
+    pub id: u64,
+    pub note_id: u64,
+    pub editor_id: u32,
+    pub edited_at: DateTime<Utc>,
+    pub title: String,
+}
+
+/// Query string of the revisions diff, a missing `to`
+/// compares with the current content of the note.
+#[derive(Deserialize)]
+pub struct RevisionsDiffQuery {
+    pub from: u64,
+    pub to: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct RevisionsDiff {
+    pub from: u64,
+    pub to: Option<u64>,
+    pub title_from: String,
+    pub title_to: String,
+    pub lines: Vec<DiffLine>,
+}
+
+impl From<NoteRevision> for NoteRevisionSummary {
+    fn from(revision: NoteRevision) -> Self {
+        NoteRevisionSummary {
+            id: revision.id,
+            note_id: revision.note_id,
+            editor_id: revision.editor_id,
+            edited_at: revision.edited_at,
+            title: revision.title,
+        }
+    }
+}
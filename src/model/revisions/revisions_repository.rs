@@ -0,0 +1,22 @@
+use async_trait::async_trait;
+
+use crate::error::StorageResult;
+use crate::model::revisions::revisions_models::{NewNoteRevision, NoteRevision};
+
+#[async_trait]
+pub trait RevisionsRepository: Send + Sync {
+    async fn insert_revision(
+        &self, new_revision: NewNoteRevision,
+    ) -> StorageResult<NoteRevision>;
+
+    async fn revision_by_id(
+        &self, revision_id: u64,
+    ) -> StorageResult<Option<NoteRevision>>;
+
+    /// Revisions of the note, oldest first.
+    async fn revisions_by_note(&self, note_id: u64) -> StorageResult<Vec<NoteRevision>>;
+
+    async fn delete_revision(
+        &self, revision_id: u64,
+    ) -> StorageResult<Option<NoteRevision>>;
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+
+use crate::error::{Error, NoteError, Result};
+use crate::model::notes::notes_models::Note;
+use crate::model::revisions::revisions_diff::diff_lines;
+use crate::model::revisions::revisions_models::{
+    NewNoteRevision,
+    NoteRevision,
+    RevisionsDiff,
+    RevisionsDiffQuery,
+};
+use crate::model::revisions::revisions_repository::RevisionsRepository;
+
+/// Storage side of the note history. Access checks are done
+/// by `NotesService`, which owns the notes themselves.
+#[derive(Clone)]
+pub struct RevisionsService {
+    repository: Arc<dyn RevisionsRepository>,
+    retention: usize,
+}
+
+impl RevisionsService {
+    pub fn new(repository: Arc<dyn RevisionsRepository>, retention: usize) -> Self {
+        Self { repository, retention }
+    }
+}
+
+impl RevisionsService {
+    /// Keeps the content the note had before the edit, dropping
+    /// the oldest revisions beyond the retention limit.
+    pub async fn record(
+        &self, note: &Note, editor_id: u32, edited_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let new_revision = NewNoteRevision {
+            note_id: note.id,
+            editor_id,
+            edited_at,
+            title: note.title.clone(),
+            body: note.body.clone(),
+        };
+
+        self.repository.insert_revision(new_revision).await
+            .map_err(|_| Error::Notes(NoteError::EditFail))?;
+
+        if self.retention == 0 {
+            return Ok(());
+        }
+
+        let revisions = self.revisions(note.id).await?;
+        let expired = revisions.len().saturating_sub(self.retention);
+
+        for revision in revisions.into_iter().take(expired) {
+            self.repository.delete_revision(revision.id).await
+                .map_err(|_| Error::Notes(NoteError::EditFail))?;
+        }
+
+        Ok(())
+    }
+
+    /// Revisions of the note, oldest first.
+    pub async fn revisions(&self, note_id: u64) -> Result<Vec<NoteRevision>> {
+        self.repository.revisions_by_note(note_id).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))
+    }
+
+    pub async fn revision(&self, note_id: u64, revision_id: u64) -> Result<NoteRevision> {
+        self.repository.revision_by_id(revision_id).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))?
+            .filter(|revision| revision.note_id == note_id)
+            .ok_or(Error::Notes(NoteError::RevisionDoesNotExists))
+    }
+
+    pub async fn diff(&self, note: &Note, query: RevisionsDiffQuery) -> Result<RevisionsDiff> {
+        let from = self.revision(note.id, query.from).await?;
+
+        let (title_to, body_to) = match query.to {
+            Some(to) => {
+                let to = self.revision(note.id, to).await?;
+                (to.title, to.body)
+            }
+            None => (note.title.clone(), note.body.clone()),
+        };
+
+        Ok(RevisionsDiff {
+            from: query.from,
+            to: query.to,
+            lines: diff_lines(&from.body, &body_to),
+            title_from: from.title,
+            title_to,
+        })
+    }
+
+    pub async fn delete_revisions(&self, note_id: u64) -> Result<()> {
+        for revision in self.revisions(note_id).await? {
+            self.repository.delete_revision(revision.id).await
+                .map_err(|_| Error::Notes(NoteError::DeleteFail))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,646 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::model::notes::notes_models::Note;
+use crate::model::search::query::Clause;
+use crate::model::search::tokenizer::{Token, Tokenizer};
+
+pub mod query;
+pub mod tokenizer;
+
+//BM25 parameters
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+const SNIPPET_TOKENS: usize = 24;
+const ELLIPSIS: &str = "…";
+
+struct Document {
+    length: usize,
+    updated_at: DateTime<Utc>,
+    stems: Vec<String>,
+    words: Vec<String>,
+}
+
+/// Inverted index over titles and bodies of all notes, ranked with BM25.
+/// Kept up to date by `NotesService` on every note mutation.
+pub struct SearchIndex {
+    tokenizer: Tokenizer,
+    postings: HashMap<String, HashMap<u64, Vec<u32>>>,
+    /// Known words with their stem and the number of notes containing them,
+    /// used to expand prefix queries
+    words: BTreeMap<String, (String, usize)>,
+    documents: HashMap<u64, Document>,
+    total_length: usize,
+}
+
+/// Notes a search ranks among, with the statistics BM25 needs.
+struct Corpus<'a> {
+    scope: Option<&'a HashSet<u64>>,
+    documents: f64,
+    average_length: f64,
+}
+
+impl Corpus<'_> {
+    fn contains(&self, note_id: u64) -> bool {
+        self.scope.is_none_or(|scope| scope.contains(&note_id))
+    }
+}
+
+pub struct SearchHit {
+    pub note_id: u64,
+    pub score: f64,
+}
+
+#[derive(Serialize)]
+pub struct SnippetFragment {
+    pub text: String,
+    pub highlight: bool,
+}
+
+impl SearchIndex {
+    pub fn new(notes: &[Note]) -> Self {
+        let mut index = Self {
+            tokenizer: Tokenizer::new(),
+            postings: HashMap::new(),
+            words: BTreeMap::new(),
+            documents: HashMap::new(),
+            total_length: 0,
+        };
+
+        for note in notes {
+            index.index_note(note);
+        }
+
+        index
+    }
+}
+
+impl SearchIndex {
+    /// Adds the note or replaces its previous version. Versions older than
+    /// the indexed one are ignored, so racing edits can not roll it back.
+    pub fn index_note(&mut self, note: &Note) {
+        let is_stale = self.documents.get(&note.id)
+            .is_some_and(|document| document.updated_at > note.updated_at);
+
+        if is_stale {
+            return;
+        }
+
+        self.remove_note(note.id);
+
+        let tokens = self.tokenizer
+            .tokenize(&format!("{}\n{}", note.title, note.body));
+
+        let mut words = HashMap::new();
+
+        for (position, token) in tokens.iter().enumerate() {
+            self.postings.entry(token.stem.clone())
+                .or_default()
+                .entry(note.id)
+                .or_default()
+                .push(position as u32);
+
+            words.insert(token.word.clone(), token.stem.clone());
+        }
+
+        for (word, stem) in words.iter() {
+            self.words.entry(word.clone())
+                .or_insert_with(|| (stem.clone(), 0))
+                .1 += 1;
+        }
+
+        let stems = words.values()
+            .cloned()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        self.total_length += tokens.len();
+        self.documents.insert(note.id, Document {
+            length: tokens.len(),
+            updated_at: note.updated_at,
+            stems,
+            words: words.into_keys().collect(),
+        });
+    }
+
+    pub fn remove_note(&mut self, note_id: u64) {
+        let Some(document) = self.documents.remove(&note_id) else {
+            return;
+        };
+
+        for stem in document.stems {
+            if let Some(postings) = self.postings.get_mut(&stem) {
+                postings.remove(&note_id);
+
+                if postings.is_empty() {
+                    self.postings.remove(&stem);
+                }
+            }
+        }
+
+        for word in document.words {
+            if let Some((_, documents)) = self.words.get_mut(&word) {
+                *documents -= 1;
+
+                if *documents == 0 {
+                    self.words.remove(&word);
+                }
+            }
+        }
+
+        self.total_length -= document.length;
+    }
+
+    pub fn parse(&self, query: &str) -> Vec<Clause> {
+        query::parse(query, &self.tokenizer)
+    }
+
+    /// Notes matching every clause, best first. Only notes in the scope
+    /// are matched and they are ranked among themselves, so notes of
+    /// others neither show up nor sway the scores. `None` searches all notes.
+    pub fn search(&self, clauses: &[Clause], scope: Option<&HashSet<u64>>) -> Vec<SearchHit> {
+        let corpus = self.corpus(scope);
+        let mut scores: Option<HashMap<u64, f64>> = None;
+
+        for clause in clauses {
+            let clause_scores = match clause {
+                Clause::Term(stem) => self.term_scores(stem, &corpus),
+                Clause::Phrase(stems) => self.phrase_scores(stems, &corpus),
+                Clause::Prefix(word) => self.prefix_scores(word, &corpus),
+            };
+
+            scores = Some(match scores {
+                None => clause_scores,
+                Some(scores) => scores.into_iter()
+                    .filter_map(|(note_id, score)| clause_scores.get(&note_id)
+                        .map(|clause_score| (note_id, score + clause_score))
+                    )
+                    .collect(),
+            });
+        }
+
+        let mut hits = scores.unwrap_or_default()
+            .into_iter()
+            .map(|(note_id, score)| SearchHit { note_id, score })
+            .collect::<Vec<_>>();
+
+        hits.sort_by(|first, second| second.score.total_cmp(&first.score)
+            .then(first.note_id.cmp(&second.note_id))
+        );
+
+        hits
+    }
+
+    /// Window of the text with the most matches, split into
+    /// highlighted and plain fragments.
+    pub fn snippet(&self, text: &str, clauses: &[Clause]) -> Vec<SnippetFragment> {
+        let tokens = self.tokenizer.tokenize(text);
+        if tokens.is_empty() {
+            return Vec::new();
+        }
+
+        let matches = tokens.iter()
+            .map(|token| is_match(token, clauses))
+            .collect::<Vec<_>>();
+
+        let window = SNIPPET_TOKENS.min(tokens.len());
+        let mut current = matches[..window].iter().filter(|matched| **matched).count();
+        let (mut best_start, mut best) = (0, current);
+
+        for start in 1..=(tokens.len() - window) {
+            current += matches[start + window - 1] as usize;
+            current -= matches[start - 1] as usize;
+
+            if current > best {
+                best = current;
+                best_start = start;
+            }
+        }
+
+        let window_tokens = &tokens[best_start..best_start + window];
+        let window_matches = &matches[best_start..best_start + window];
+
+        let mut fragments = Vec::new();
+        if best_start > 0 {
+            push_fragment(&mut fragments, ELLIPSIS, false);
+        }
+
+        let mut position = window_tokens[0].start;
+        for (token, matched) in window_tokens.iter().zip(window_matches) {
+            if *matched {
+                push_fragment(&mut fragments, &text[position..token.start], false);
+                push_fragment(&mut fragments, &text[token.start..token.end], true);
+                position = token.end;
+            }
+        }
+
+        push_fragment(
+            &mut fragments,
+            &text[position..window_tokens[window - 1].end],
+            false,
+        );
+
+        if best_start + window < tokens.len() {
+            push_fragment(&mut fragments, ELLIPSIS, false);
+        }
+
+        fragments
+    }
+
+    fn corpus<'a>(&self, scope: Option<&'a HashSet<u64>>) -> Corpus<'a> {
+        let (documents, total_length) = match scope {
+            None => (self.documents.len(), self.total_length),
+            Some(scope) => scope.iter()
+                .filter_map(|note_id| self.documents.get(note_id))
+                .fold((0, 0), |(documents, total_length), document| {
+                    (documents + 1, total_length + document.length)
+                }),
+        };
+
+        Corpus {
+            scope,
+            documents: documents as f64,
+            average_length: total_length as f64 / documents.max(1) as f64,
+        }
+    }
+
+    fn document_frequency(postings: &HashMap<u64, Vec<u32>>, corpus: &Corpus) -> usize {
+        match corpus.scope {
+            None => postings.len(),
+            Some(_) => postings.keys()
+                .filter(|note_id| corpus.contains(**note_id))
+                .count(),
+        }
+    }
+
+    fn term_scores(&self, stem: &str, corpus: &Corpus) -> HashMap<u64, f64> {
+        let Some(postings) = self.postings.get(stem) else {
+            return HashMap::new();
+        };
+
+        let document_frequency = Self::document_frequency(postings, corpus);
+
+        postings.iter()
+            .filter(|(note_id, _)| corpus.contains(**note_id))
+            .map(|(note_id, positions)| (
+                *note_id,
+                self.bm25(positions.len(), document_frequency, *note_id, corpus),
+            ))
+            .collect()
+    }
+
+    fn phrase_scores(&self, stems: &[String], corpus: &Corpus) -> HashMap<u64, f64> {
+        let Some(postings) = stems.iter()
+            .map(|stem| self.postings.get(stem))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return HashMap::new();
+        };
+
+        let document_frequencies = postings.iter()
+            .map(|term_postings| Self::document_frequency(term_postings, corpus))
+            .collect::<Vec<_>>();
+
+        postings[0].iter()
+            .filter(|(note_id, _)| corpus.contains(**note_id))
+            .filter(|(note_id, first_positions)| first_positions.iter()
+                .any(|position| postings[1..].iter().enumerate()
+                    .all(|(offset, term_postings)| term_postings.get(note_id)
+                        .is_some_and(|positions| positions
+                            .binary_search(&(position + offset as u32 + 1))
+                            .is_ok()
+                        )
+                    )
+                )
+            )
+            .map(|(note_id, _)| {
+                let score = postings.iter()
+                    .zip(&document_frequencies)
+                    .map(|(term_postings, document_frequency)| self.bm25(
+                        term_postings[note_id].len(),
+                        *document_frequency,
+                        *note_id,
+                        corpus,
+                    ))
+                    .sum();
+
+                (*note_id, score)
+            })
+            .collect()
+    }
+
+    fn prefix_scores(&self, prefix: &str, corpus: &Corpus) -> HashMap<u64, f64> {
+        let stems = self.words.range(prefix.to_string()..)
+            .take_while(|(word, _)| word.starts_with(prefix))
+            .map(|(_, (stem, _))| stem)
+            .collect::<HashSet<_>>();
+
+        let mut scores = HashMap::new();
+
+        for stem in stems {
+            for (note_id, score) in self.term_scores(stem, corpus) {
+                let best: &mut f64 = scores.entry(note_id).or_default();
+                *best = best.max(score);
+            }
+        }
+
+        scores
+    }
+
+    fn bm25(
+        &self, term_frequency: usize, document_frequency: usize, note_id: u64, corpus: &Corpus,
+    ) -> f64 {
+        let documents = corpus.documents;
+        let average_length = corpus.average_length;
+        let length = self.documents.get(&note_id)
+            .map_or(average_length, |document| document.length as f64);
+
+        let document_frequency = document_frequency as f64;
+        let idf = (1.0 + (documents - document_frequency + 0.5)
+            / (document_frequency + 0.5)).ln();
+
+        let term_frequency = term_frequency as f64;
+
+        idf * term_frequency * (K1 + 1.0)
+            / (term_frequency + K1 * (1.0 - B + B * length / average_length))
+    }
+}
+
+fn is_match(token: &Token, clauses: &[Clause]) -> bool {
+    clauses.iter().any(|clause| match clause {
+        Clause::Term(stem) => token.stem == *stem,
+        Clause::Phrase(stems) => stems.contains(&token.stem),
+        Clause::Prefix(prefix) => token.word.starts_with(prefix.as_str()),
+    })
+}
+
+fn push_fragment(fragments: &mut Vec<SnippetFragment>, text: &str, highlight: bool) {
+    if text.is_empty() {
+        return;
+    }
+
+    match fragments.last_mut() {
+        Some(last) if !last.highlight && !highlight => last.text.push_str(text),
+        _ => fragments.push(SnippetFragment { text: text.to_string(), highlight }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::model::notes::notes_models::INITIAL_NOTE_VERSION;
+
+    fn note(id: u64, title: &str, body: &str) -> Note {
+        Note {
+            id,
+            creator_id: 1,
+            title: title.to_string(),
+            body: body.to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_editor_id: 1,
+            tags: vec![],
+            notebook_id: None,
+            version: INITIAL_NOTE_VERSION,
+            deleted_at: None,
+        }
+    }
+
+    fn hits(index: &SearchIndex, query: &str, scope: Option<&HashSet<u64>>) -> Vec<SearchHit> {
+        index.search(&index.parse(query), scope)
+    }
+
+    fn ids(index: &SearchIndex, query: &str) -> Vec<u64> {
+        hits(index, query, None).into_iter()
+            .map(|hit| hit.note_id)
+            .collect()
+    }
+
+    fn fragments(snippet: &[SnippetFragment]) -> Vec<(&str, bool)> {
+        snippet.iter()
+            .map(|fragment| (fragment.text.as_str(), fragment.highlight))
+            .collect()
+    }
+
+    #[test]
+    fn english_words_match_by_stem() {
+        let index = SearchIndex::new(&[
+            note(1, "Running", "The runner runs"),
+            note(2, "Walking", "Nothing else"),
+        ]);
+
+        assert_eq!(ids(&index, "run"), [1]);
+        assert_eq!(ids(&index, "RUNS"), [1]);
+        assert!(ids(&index, "runway").is_empty());
+    }
+
+    #[test]
+    fn russian_words_match_by_stem_with_yo_folded() {
+        let index = SearchIndex::new(&[
+            note(1, "Ёлка", "Зелёные ёлки в лесу"),
+            note(2, "Кошки", "Кошка спит"),
+        ]);
+
+        assert_eq!(ids(&index, "елка"), [1]);
+        assert_eq!(ids(&index, "ЕЛКАМИ"), [1]);
+        assert_eq!(ids(&index, "зеленый"), [1]);
+        assert_eq!(ids(&index, "кошкой"), [2]);
+    }
+
+    #[test]
+    fn every_clause_must_match() {
+        let index = SearchIndex::new(&[
+            note(1, "Rust", "Ownership and borrowing"),
+            note(2, "Rust", "Traits"),
+        ]);
+
+        assert_eq!(ids(&index, "rust ownership"), [1]);
+        assert!(ids(&index, "rust missing").is_empty());
+        assert!(ids(&index, "").is_empty());
+    }
+
+    #[test]
+    fn bm25_ranks_frequent_terms_and_short_notes_first() {
+        let index = SearchIndex::new(&[
+            note(1, "One", "cache one two three four five"),
+            note(2, "Two", "cache cache cache two three four"),
+            note(3, "Three", "cache"),
+            note(4, "Four", "unrelated words only here"),
+        ]);
+
+        //Both beat the note with one occurrence among four other words
+        assert_eq!(ids(&index, "cache"), [2, 3, 1]);
+
+        let scores = hits(&index, "cache", None);
+        assert!(scores.windows(2).all(|pair| pair[0].score > pair[1].score));
+    }
+
+    #[test]
+    fn rare_terms_weigh_more() {
+        let index = SearchIndex::new(&[
+            note(1, "A", "common rare"),
+            note(2, "B", "common other"),
+            note(3, "C", "common words"),
+        ]);
+
+        let common = hits(&index, "common", None)[0].score;
+        let rare = hits(&index, "rare", None)[0].score;
+
+        assert!(rare > common);
+    }
+
+    #[test]
+    fn phrase_requires_adjacent_words_in_order() {
+        let index = SearchIndex::new(&[
+            note(1, "Adjacent", "the borrow checker complains"),
+            note(2, "Reversed", "checker borrow"),
+            note(3, "Apart", "borrow the checker"),
+            note(4, "Repeated", "borrow borrow and then borrow checker"),
+        ]);
+
+        assert_eq!(ids(&index, "\"borrow checker\"").len(), 2);
+        assert!(ids(&index, "\"borrow checker\"").iter().all(|id| [1, 4].contains(id)));
+        assert_eq!(ids(&index, "\"borrowing checkers\"").len(), 2);
+        assert!(ids(&index, "\"checker complains borrow\"").is_empty());
+        assert!(ids(&index, "\"borrow missing\"").is_empty());
+    }
+
+    #[test]
+    fn prefix_expands_over_indexed_words() {
+        let index = SearchIndex::new(&[
+            note(1, "Async", "asynchronous runtime"),
+            note(2, "Await", "async functions"),
+            note(3, "Sync", "synchronous code"),
+        ]);
+
+        let mut matched = ids(&index, "asyn*");
+        matched.sort();
+
+        assert_eq!(matched, [1, 2]);
+        assert_eq!(ids(&index, "sync*"), [3]);
+        assert!(ids(&index, "asyncz*").is_empty());
+    }
+
+    #[test]
+    fn scoped_search_ranks_among_scoped_notes_only() {
+        let scoped = [
+            note(1, "Mine", "shared term here"),
+            note(2, "Mine", "another note"),
+        ];
+
+        let mut all = scoped.to_vec();
+        all.extend((3..20).map(|id| note(id, "Others", "shared term shared term everywhere")));
+
+        let index = SearchIndex::new(&all);
+        let alone = SearchIndex::new(&scoped);
+        let scope = HashSet::from([1, 2]);
+
+        let scoped_hits = hits(&index, "term", Some(&scope));
+        let alone_hits = hits(&alone, "term", None);
+
+        assert_eq!(scoped_hits.len(), 1);
+        assert_eq!(scoped_hits[0].note_id, 1);
+        assert_eq!(scoped_hits[0].score, alone_hits[0].score);
+
+        let shared_hits = hits(&index, "\"shared term\" another*", Some(&scope));
+        assert!(shared_hits.is_empty());
+
+        let prefix_hits = hits(&index, "shar*", Some(&scope));
+        let alone_prefix_hits = hits(&alone, "shar*", None);
+        assert_eq!(prefix_hits[0].score, alone_prefix_hits[0].score);
+        assert!(hits(&index, "everywhere", Some(&scope)).is_empty());
+    }
+
+    #[test]
+    fn stale_versions_are_ignored() {
+        let current = note(1, "Current", "fresh words");
+        let mut stale = note(1, "Stale", "old words");
+        stale.updated_at = current.updated_at - Duration::seconds(1);
+
+        let mut index = SearchIndex::new(std::slice::from_ref(&current));
+        index.index_note(&stale);
+
+        assert_eq!(ids(&index, "fresh"), [1]);
+        assert!(ids(&index, "old").is_empty());
+        assert!(ids(&index, "stal*").is_empty());
+
+        let mut newer = note(1, "Newer", "new words");
+        newer.updated_at = current.updated_at + Duration::seconds(1);
+        index.index_note(&newer);
+
+        assert_eq!(ids(&index, "new"), [1]);
+        assert!(ids(&index, "fresh").is_empty());
+        assert!(ids(&index, "fres*").is_empty());
+    }
+
+    #[test]
+    fn removed_notes_are_not_found() {
+        let mut index = SearchIndex::new(&[
+            note(1, "Kept", "shared"),
+            note(2, "Removed", "shared unique"),
+        ]);
+
+        index.remove_note(2);
+        index.remove_note(2);
+
+        assert_eq!(ids(&index, "shared"), [1]);
+        assert!(ids(&index, "unique").is_empty());
+        assert!(ids(&index, "uniq*").is_empty());
+        assert_eq!(index.total_length, index.documents[&1].length);
+    }
+
+    #[test]
+    fn snippet_of_short_text_has_no_ellipsis() {
+        let index = SearchIndex::new(&[]);
+        let clauses = index.parse("cats");
+
+        assert_eq!(
+            fragments(&index.snippet("Cats and dogs, cats!", &clauses)),
+            [("Cats", true), (" and dogs, ", false), ("cats", true)],
+        );
+        assert!(index.snippet("", &clauses).is_empty());
+        assert!(index.snippet("...", &clauses).is_empty());
+    }
+
+    #[test]
+    fn snippet_slides_to_the_densest_window() {
+        let index = SearchIndex::new(&[]);
+        let clauses = index.parse("needle");
+
+        let filler = |from: usize, count: usize| (from..from + count)
+            .map(|number| format!("w{number}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let middle = format!("{} needle needle {}", filler(0, 40), filler(40, 40));
+        let snippet = index.snippet(&middle, &clauses);
+        assert!(snippet.first().unwrap().text.starts_with(&format!("{ELLIPSIS}w18 ")));
+        //The first window holding both matches wins, ending on the second one
+        assert_eq!(
+            fragments(&snippet[snippet.len() - 3..]),
+            [(" ", false), ("needle", true), (ELLIPSIS, false)],
+        );
+
+        let start = format!("needle {}", filler(0, 40));
+        let snippet = index.snippet(&start, &clauses);
+        assert_eq!(fragments(&snippet[..1]), [("needle", true)]);
+        assert!(snippet.last().unwrap().text.ends_with(&format!(" w22{ELLIPSIS}")));
+
+        let end = format!("{} needle", filler(0, 40));
+        let snippet = index.snippet(&end, &clauses);
+        assert!(snippet.first().unwrap().text.starts_with(&format!("{ELLIPSIS}w17 ")));
+        assert_eq!(fragments(&snippet[snippet.len() - 1..]), [("needle", true)]);
+
+        let without_match = filler(0, 40);
+        let snippet = index.snippet(&without_match, &clauses);
+        assert_eq!(fragments(&snippet), [
+            (format!("{}{ELLIPSIS}", filler(0, SNIPPET_TOKENS)).as_str(), false),
+        ]);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::model::search::tokenizer::Tokenizer;
+
+/// Part of a search query every matching note must satisfy.
+#[derive(Debug, PartialEq)]
+pub enum Clause {
+    /// Single word, matched by its stem
+    Term(String),
+    /// Quoted words, matched by consecutive stems
+    Phrase(Vec<String>),
+    /// Word ending with `*`, matched by the beginning of words
+    Prefix(String),
+}
+
+/// Parses queries like `rust "borrow checker" async*`.
+pub fn parse(query: &str, tokenizer: &Tokenizer) -> Vec<Clause> {
+    let mut clauses = Vec::new();
+
+    for (index, part) in query.split('"').enumerate() {
+        let is_quoted = index % 2 == 1;
+
+        if is_quoted {
+            let stems = tokenizer.tokenize(part).into_iter()
+                .map(|token| token.stem)
+                .collect::<Vec<_>>();
+
+            match stems.len() {
+                0 => {}
+                1 => clauses.extend(stems.into_iter().map(Clause::Term)),
+                _ => clauses.push(Clause::Phrase(stems)),
+            }
+
+            continue;
+        }
+
+        for word in part.split_whitespace() {
+            let mut tokens = tokenizer.tokenize(word);
+
+            let prefix = word.ends_with('*')
+                .then(|| tokens.pop())
+                .flatten();
+
+            clauses.extend(tokens.into_iter().map(|token| Clause::Term(token.stem)));
+            clauses.extend(prefix.map(|token| Clause::Prefix(token.word)));
+        }
+    }
+
+    clauses
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::*;
+    use crate::model::notes::notes_models::{Note, INITIAL_NOTE_VERSION};
+    use crate::model::search::SearchIndex;
+
+    fn clauses(query: &str) -> Vec<Clause> {
+        parse(query, &Tokenizer::new())
+    }
+
+    fn term(word: &str) -> Clause {
+        Clause::Term(Tokenizer::new().stem(word))
+    }
+
+    fn note(id: u64, body: &str) -> Note {
+        Note {
+            id,
+            creator_id: 1,
+            title: String::new(),
+            body: body.to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now() + Duration::seconds(id as i64),
+            last_editor_id: 1,
+            tags: vec![],
+            notebook_id: None,
+            version: INITIAL_NOTE_VERSION,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn parses_terms_phrases_and_prefixes() {
+        assert_eq!(
+            clauses(r#"Rust "borrow checker" async*"#),
+            [
+                term("rust"),
+                Clause::Phrase(vec![
+                    Tokenizer::new().stem("borrow"),
+                    Tokenizer::new().stem("checker"),
+                ]),
+                Clause::Prefix("async".to_string()),
+            ],
+        );
+    }
+
+    #[test]
+    fn single_quoted_word_is_a_term() {
+        assert_eq!(clauses(r#""Running""#), [term("running")]);
+    }
+
+    #[test]
+    fn empty_quotes_and_punctuation_are_ignored() {
+        assert_eq!(clauses(r#""" ... rust"#), [term("rust")]);
+        assert!(clauses("  ").is_empty());
+    }
+
+    #[test]
+    fn unclosed_quote_is_a_phrase_to_the_end() {
+        assert_eq!(
+            clauses(r#"rust "borrow checker"#),
+            [
+                term("rust"),
+                Clause::Phrase(vec![
+                    Tokenizer::new().stem("borrow"),
+                    Tokenizer::new().stem("checker"),
+                ]),
+            ],
+        );
+    }
+
+    #[test]
+    fn prefix_is_normalized_and_only_applies_to_the_last_word_part() {
+        assert_eq!(
+            clauses("Foo-ЁЛ*"),
+            [term("foo"), Clause::Prefix("ел".to_string())],
+        );
+    }
+
+    #[test]
+    fn parsed_queries_search_built_index() {
+        let index = SearchIndex::new(&[
+            note(1, "The borrow checker rejects the program"),
+            note(2, "Borrow a checker board"),
+            note(3, "Asynchronous programs in Rust"),
+        ]);
+
+        let search = |query: &str| index.search(&index.parse(query), None)
+            .into_iter()
+            .map(|hit| hit.note_id)
+            .collect::<Vec<_>>();
+
+        assert_eq!(search(r#""borrow checker""#), [1]);
+        //Both words anywhere, the shorter note first
+        assert_eq!(search("borrow checker"), [2, 1]);
+        assert_eq!(search("program*"), [3, 1]);
+        assert_eq!(search("rust async*"), [3]);
+        assert!(search("missing").is_empty());
+    }
+}
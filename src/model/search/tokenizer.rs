@@ -0,0 +1,69 @@
+use rust_stemmers::{Algorithm, Stemmer};
+
+/// Word of a text with its byte range in that text.
+pub struct Token {
+    pub word: String,
+    pub stem: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Splits text into lowercased words and stems them with the English
+/// or the Russian stemmer, depending on the alphabet of each word.
+pub struct Tokenizer {
+    english: Stemmer,
+    russian: Stemmer,
+}
+
+impl Tokenizer {
+    pub fn new() -> Self {
+        Self {
+            english: Stemmer::create(Algorithm::English),
+            russian: Stemmer::create(Algorithm::Russian),
+        }
+    }
+}
+
+impl Tokenizer {
+    pub fn tokenize(&self, text: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut word_start = None;
+
+        for (index, character) in text.char_indices() {
+            match (character.is_alphanumeric(), word_start) {
+                (true, None) => word_start = Some(index),
+                (false, Some(start)) => {
+                    tokens.push(self.token(text, start, index));
+                    word_start = None;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(start) = word_start {
+            tokens.push(self.token(text, start, text.len()));
+        }
+
+        tokens
+    }
+
+    pub fn normalize(&self, word: &str) -> String {
+        word.to_lowercase().replace('ё', "е")
+    }
+
+    pub fn stem(&self, normalized_word: &str) -> String {
+        let is_cyrillic = normalized_word.chars()
+            .any(|character| matches!(character, 'а'..='я'));
+
+        let stemmer = if is_cyrillic { &self.russian } else { &self.english };
+
+        stemmer.stem(normalized_word).into_owned()
+    }
+
+    fn token(&self, text: &str, start: usize, end: usize) -> Token {
+        let word = self.normalize(&text[start..end]);
+        let stem = self.stem(&word);
+
+        Token { word, stem, start, end }
+    }
+}
@@ -1,2 +1,3 @@
 pub mod sessions_service;
-pub mod sessions_models;
\ No newline at end of file
+pub mod sessions_models;
+pub mod sessions_repository;
\ No newline at end of file
@@ -1,6 +1,85 @@
-#[derive(Clone)]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Single login of a user, one per device. Tokens name the session
+/// they were issued for, so sessions are checked and revoked separately.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Session {
     pub id: u32,
     pub user_id: u32,
+    #[serde(default)]
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_seen_at: DateTime<Utc>,
     pub expires_at: usize,
-}
\ No newline at end of file
+    #[serde(default)]
+    pub device_label: Option<String>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    pub ip: Option<String>,
+}
+
+pub struct NewSession {
+    pub user_id: u32,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: usize,
+    pub client: SessionClient,
+}
+
+/// Opaque single use token trading itself for a new access token
+/// and its own successor. Tokens of a session form one family.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RefreshToken {
+    pub id: u64,
+    pub session_id: u32,
+    /// Only the hash is kept, the token itself lives in the client's cookie
+    #[serde(alias = "token")]
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+pub struct NewRefreshToken {
+    pub session_id: u32,
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Where a login comes from, as told by the request.
+#[derive(Clone, Default)]
+pub struct SessionClient {
+    pub device_label: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+/// Session as listed to its user.
+#[derive(Serialize)]
+pub struct SessionDetails {
+    pub id: u32,
+    pub device_label: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Session of the request listing the sessions
+    pub current: bool,
+}
+
+impl SessionDetails {
+    pub fn new(session: Session, current_session_id: Option<u32>) -> Self {
+        SessionDetails {
+            id: session.id,
+            current: current_session_id == Some(session.id),
+            device_label: session.device_label,
+            user_agent: session.user_agent,
+            ip: session.ip,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+            expires_at: DateTime::from_timestamp(session.expires_at as i64, 0)
+                .unwrap_or_default(),
+        }
+    }
+}
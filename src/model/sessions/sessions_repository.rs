@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::error::StorageResult;
+use crate::model::sessions::sessions_models::{
+    NewRefreshToken,
+    NewSession,
+    RefreshToken,
+    Session,
+};
+
+#[async_trait]
+pub trait SessionsRepository: Send + Sync {
+    async fn insert_session(
+        &self, new_session: NewSession,
+    ) -> StorageResult<Session>;
+
+    async fn session_by_id(
+        &self, session_id: u32,
+    ) -> StorageResult<Option<Session>>;
+
+    async fn sessions_by_user(&self, user_id: u32) -> StorageResult<Vec<Session>>;
+
+    async fn update_session(
+        &self, session: Session,
+    ) -> StorageResult<Option<Session>>;
+
+    async fn delete_session(
+        &self, session_id: u32,
+    ) -> StorageResult<Option<Session>>;
+}
+
+#[async_trait]
+pub trait RefreshTokensRepository: Send + Sync {
+    async fn insert_refresh_token(
+        &self, new_refresh_token: NewRefreshToken,
+    ) -> StorageResult<RefreshToken>;
+
+    async fn refresh_token_by_hash(
+        &self, token_hash: &str,
+    ) -> StorageResult<Option<RefreshToken>>;
+
+    async fn refresh_tokens_by_session(
+        &self, session_id: u32,
+    ) -> StorageResult<Vec<RefreshToken>>;
+
+    /// Marks the token used, unless it already is. Only the caller
+    /// getting the token back may rotate it.
+    async fn use_refresh_token(
+        &self, refresh_token_id: u64, used_at: DateTime<Utc>,
+    ) -> StorageResult<Option<RefreshToken>>;
+
+    async fn delete_refresh_token(
+        &self, refresh_token_id: u64,
+    ) -> StorageResult<Option<RefreshToken>>;
+}
@@ -1,92 +1,411 @@
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+
 use chrono::{Duration, Utc};
-use crate::model::sessions::sessions_models::Session;
-use crate::error::{Result, Error, SessionError};
+
+use crate::context::AuthTokenContext;
+use crate::error::{Error, Result, SessionError, StorageResult};
+use crate::model::sessions::sessions_models::{
+    NewRefreshToken,
+    NewSession,
+    Session,
+    SessionClient,
+    SessionDetails,
+};
+use crate::model::sessions::sessions_repository::{RefreshTokensRepository, SessionsRepository};
+use crate::model::tokens::{generate_token, hash_token};
+
+/// Last use of a session is stored at most this often,
+/// so not every request turns into a write.
+const LAST_SEEN_PRECISION_SECONDS: i64 = 60;
 
 #[derive(Clone)]
 pub struct SessionsService {
-    sessions_collection: Arc<Mutex<Vec<Option<Session>>>>,
+    repository: Arc<dyn SessionsRepository>,
+    refresh_tokens: Arc<dyn RefreshTokensRepository>,
 }
 
 impl SessionsService {
-    pub fn new() -> Self {
-        Self { sessions_collection: Arc::default() }
+    pub fn new(
+        repository: Arc<dyn SessionsRepository>,
+        refresh_tokens: Arc<dyn RefreshTokensRepository>,
+    ) -> Self {
+        Self { repository, refresh_tokens }
     }
 }
 
 impl SessionsService {
-    pub async fn create_or_update_session(&self, user_id: u32, validity_days: u16) -> Result<Session> {
-        let mut collection = self.sessions_collection.lock()
-            .map_err(|_| Error::Sessions(SessionError::CreateFail))?;
-
-        let previous_session_id = collection.iter()
-            .find(|session_option| {
-                matches!(session_option, Some(session) if session.user_id == user_id)
-            })
-            .and_then(|session_option| session_option.as_ref()
-                .map(|session| session.id)
-            );
-
-        let expiration_time = Utc::now() + Duration::days(validity_days as i64);
-
-        if let Some(id) = previous_session_id {
-            let session = collection[id as usize].take().unwrap();
-            
-            let updated_session = Session {
-                expires_at: expiration_time.timestamp() as usize,
-                ..session
-            };
-            
-            collection[id as usize] = Some(session.clone());
-            
-            return Ok(updated_session);
+    /// Starts a new session, leaving other sessions of the user intact
+    /// apart from the expired ones, which are dropped on the way.
+    pub async fn create_session(
+        &self, user_id: u32, validity_days: u16, client: SessionClient,
+    ) -> Result<Session> {
+        let now = Utc::now();
+
+        let expired_sessions = self.repository.sessions_by_user(user_id).await
+            .map_err(|_| Error::Sessions(SessionError::CreateFail))?
+            .into_iter()
+            .filter(|session| session.expires_at < now.timestamp() as usize);
+
+        for session in expired_sessions {
+            self.delete_session(session.id).await
+                .map_err(|_| Error::Sessions(SessionError::CreateFail))?;
         }
-        
-        let session = Session {
-            id: collection.len() as u32,
+
+        let new_session = NewSession {
             user_id,
-            expires_at: expiration_time.timestamp() as usize,
+            created_at: now,
+            expires_at: (now + Duration::days(validity_days as i64)).timestamp() as usize,
+            client,
         };
 
-        collection.push(Some(session.clone()));
+        self.repository.insert_session(new_session).await
+            .map_err(|_| Error::Sessions(SessionError::CreateFail))
+    }
+
+    /// Unexpired sessions of the user, most recently used first.
+    pub async fn sessions_of_user(
+        &self, context: &AuthTokenContext,
+    ) -> Result<Vec<SessionDetails>> {
+        let now = Utc::now().timestamp() as usize;
+
+        let mut sessions = self.repository.sessions_by_user(context.user_id()).await
+            .map_err(|_| Error::Sessions(SessionError::ReceiveFail))?
+            .into_iter()
+            .filter(|session| session.expires_at >= now)
+            .collect::<Vec<_>>();
+
+        sessions.sort_by(|first, second| second.last_seen_at.cmp(&first.last_seen_at)
+            .then(second.id.cmp(&first.id))
+        );
+
+        let sessions = sessions.into_iter()
+            .map(|session| SessionDetails::new(session, context.session_id()))
+            .collect();
+
+        Ok(sessions)
+    }
+
+    /// Ends one of the user's sessions, tokens of which are
+    /// rejected from the very next request on.
+    pub async fn revoke_session(
+        &self, context: &AuthTokenContext, session_id: u32,
+    ) -> Result<SessionDetails> {
+        self.repository.session_by_id(session_id).await
+            .map_err(|_| Error::Sessions(SessionError::DeleteFail))?
+            .filter(|session| session.user_id == context.user_id())
+            .ok_or(Error::Sessions(SessionError::SessionDoesNotExists))?;
+
+        let session = self.delete_session(session_id).await?
+            .ok_or(Error::Sessions(SessionError::SessionDoesNotExists))?;
+
+        Ok(SessionDetails::new(session, context.session_id()))
+    }
+
+    /// Ends every session of the user except the current one.
+    pub async fn revoke_other_sessions(
+        &self, context: &AuthTokenContext,
+    ) -> Result<Vec<SessionDetails>> {
+        let other_sessions = self.repository.sessions_by_user(context.user_id()).await
+            .map_err(|_| Error::Sessions(SessionError::DeleteFail))?
+            .into_iter()
+            .filter(|session| Some(session.id) != context.session_id());
+
+        let mut revoked_sessions = Vec::new();
 
-        Ok(session)
+        for session in other_sessions {
+            let session = self.delete_session(session.id).await?;
+
+            if let Some(session) = session {
+                revoked_sessions.push(SessionDetails::new(session, context.session_id()));
+            }
+        }
+
+        Ok(revoked_sessions)
     }
 
-    pub async fn delete_session(&self, user_id: u32) -> Result<()> {
-        let mut collection = self.sessions_collection.lock()
+    /// Ends the session if it is still there, so logging out twice is fine.
+    pub async fn end_session(&self, session_id: u32, user_id: u32) -> Result<()> {
+        let session = self.repository.session_by_id(session_id).await
+            .map_err(|_| Error::Sessions(SessionError::DeleteFail))?
+            .filter(|session| session.user_id == user_id);
+
+        if let Some(session) = session {
+            self.delete_session(session.id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Ends every session of the user, e.g. when the account is deleted.
+    pub async fn delete_sessions_of_user(&self, user_id: u32) -> Result<()> {
+        self.delete_other_sessions_of_user(user_id, None).await
+    }
+
+    /// Ends every session of the user but the given one, e.g. when the
+    /// password is changed from that session.
+    pub async fn delete_other_sessions_of_user(
+        &self, user_id: u32, current_session_id: Option<u32>,
+    ) -> Result<()> {
+        let sessions = self.repository.sessions_by_user(user_id).await
+            .map_err(|_| Error::Sessions(SessionError::DeleteFail))?
+            .into_iter()
+            .filter(|session| Some(session.id) != current_session_id);
+
+        for session in sessions {
+            self.delete_session(session.id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Ends the session a refresh token belongs to, so logging out
+    /// works after the access token has expired.
+    pub async fn end_session_of_refresh_token(&self, token: &str) -> Result<()> {
+        let refresh_token = self.refresh_tokens.refresh_token_by_hash(&hash_token(token)).await
             .map_err(|_| Error::Sessions(SessionError::DeleteFail))?;
 
-        let session_id = collection.iter()
-            .filter_map(|session_option| {
-                match session_option.as_ref() {
-                    Some(session)
-                    if session.user_id == user_id => Some(session.id),
-                    _ => None
-                }
-            })
-            .next();
-
-        if let Some(id) = session_id {
-            collection[id as usize].take();
+        if let Some(refresh_token) = refresh_token {
+            self.delete_session(refresh_token.session_id).await?;
         }
 
         Ok(())
     }
 
-    pub async fn session_validity(
-        &self, session_id: u32, token_expires_at: usize,
-    ) -> Result<u32> {
-        let collection = self.sessions_collection.lock()
-            .map_err(|_| Error::Sessions(SessionError::ValidityCheckFail))?;
+    /// First refresh token of a freshly created session.
+    pub async fn issue_refresh_token(&self, session: &Session) -> Result<String> {
+        self.insert_refresh_token(session.id).await
+            .map_err(|_| Error::Sessions(SessionError::CreateFail))
+    }
+
+    /// Trades the refresh token for its successor. A token presented
+    /// a second time means it has leaked, so the whole session is ended
+    /// and neither the thief nor the owner can refresh it anymore.
+    pub async fn refresh(&self, token: &str) -> Result<(Session, String)> {
+        let refresh_token = self.refresh_tokens.refresh_token_by_hash(&hash_token(token)).await
+            .map_err(|_| Error::Sessions(SessionError::RefreshFail))?
+            .ok_or(Error::Sessions(SessionError::RefreshTokenInvalid))?;
+
+        let session = self.repository.session_by_id(refresh_token.session_id).await
+            .map_err(|_| Error::Sessions(SessionError::RefreshFail))?
+            .ok_or(Error::Sessions(SessionError::RefreshTokenInvalid))?;
+
+        let now = Utc::now();
 
-        let session = collection[session_id as usize].as_ref()
+        if session.expires_at < now.timestamp() as usize {
+            return Err(Error::Sessions(SessionError::RefreshTokenInvalid));
+        }
+
+        let used_refresh_token = self.refresh_tokens
+            .use_refresh_token(refresh_token.id, now).await
+            .map_err(|_| Error::Sessions(SessionError::RefreshFail))?;
+
+        if used_refresh_token.is_none() {
+            self.delete_session(session.id).await
+                .map_err(|_| Error::Sessions(SessionError::RefreshFail))?;
+
+            return Err(Error::Sessions(SessionError::RefreshTokenReused));
+        }
+
+        let next_token = self.insert_refresh_token(session.id).await
+            .map_err(|_| Error::Sessions(SessionError::RefreshFail))?;
+
+        Ok((session, next_token))
+    }
+
+    /// Checks that the session the token was issued for still exists
+    /// and belongs to the token's user, noting its use.
+    pub async fn session_validity(&self, session_id: u32, user_id: u32) -> Result<u32> {
+        let session = self.repository.session_by_id(session_id).await
+            .map_err(|_| Error::Sessions(SessionError::ValidityCheckFail))?
             .ok_or(Error::Sessions(SessionError::SessionDoesNotExists))?;
 
-        if session.expires_at == token_expires_at {
-            Ok(session.user_id)
-        } else {
-            Err(Error::Sessions(SessionError::SessionInvalid))
+        let now = Utc::now();
+
+        if session.user_id != user_id || session.expires_at < now.timestamp() as usize {
+            return Err(Error::Sessions(SessionError::SessionInvalid));
+        }
+
+        if now - session.last_seen_at >= Duration::seconds(LAST_SEEN_PRECISION_SECONDS) {
+            let seen_session = Session { last_seen_at: now, ..session };
+
+            self.repository.update_session(seen_session).await
+                .map_err(|_| Error::Sessions(SessionError::ValidityCheckFail))?
+                .ok_or(Error::Sessions(SessionError::SessionInvalid))?;
         }
+
+        Ok(user_id)
+    }
+
+    /// Stores the hash of a new refresh token, returning the token itself.
+    async fn insert_refresh_token(&self, session_id: u32) -> StorageResult<String> {
+        let token = generate_token();
+
+        let new_refresh_token = NewRefreshToken {
+            session_id,
+            token_hash: hash_token(&token),
+            created_at: Utc::now(),
+        };
+
+        self.refresh_tokens.insert_refresh_token(new_refresh_token).await?;
+
+        Ok(token)
+    }
+
+    /// Drops the session together with its family of refresh tokens.
+    async fn delete_session(&self, session_id: u32) -> Result<Option<Session>> {
+        let refresh_tokens = self.refresh_tokens.refresh_tokens_by_session(session_id).await
+            .map_err(|_| Error::Sessions(SessionError::DeleteFail))?;
+
+        for refresh_token in refresh_tokens {
+            self.refresh_tokens.delete_refresh_token(refresh_token.id).await
+                .map_err(|_| Error::Sessions(SessionError::DeleteFail))?;
+        }
+
+        self.repository.delete_session(session_id).await
+            .map_err(|_| Error::Sessions(SessionError::DeleteFail))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sessions::sessions_models::RefreshToken;
+    use crate::model::storage::memory::{
+        InMemoryRefreshTokensRepository,
+        InMemorySessionsRepository,
+    };
+
+    const USER_ID: u32 = 1;
+
+    struct Fixture {
+        service: SessionsService,
+        sessions: Arc<InMemorySessionsRepository>,
+        refresh_tokens: Arc<InMemoryRefreshTokensRepository>,
+    }
+
+    fn fixture() -> Fixture {
+        let sessions = Arc::new(InMemorySessionsRepository::default());
+        let refresh_tokens = Arc::new(InMemoryRefreshTokensRepository::default());
+
+        Fixture {
+            service: SessionsService::new(sessions.clone(), refresh_tokens.clone()),
+            sessions,
+            refresh_tokens,
+        }
+    }
+
+    impl Fixture {
+        async fn login(&self) -> (Session, String) {
+            let session = self.service
+                .create_session(USER_ID, 1, SessionClient::default()).await
+                .unwrap();
+            let token = self.service.issue_refresh_token(&session).await.unwrap();
+
+            (session, token)
+        }
+
+        async fn family(&self, session_id: u32) -> Vec<RefreshToken> {
+            self.refresh_tokens.refresh_tokens_by_session(session_id).await.unwrap()
+        }
+    }
+
+    fn is_error(result: Result<(Session, String)>, expected: SessionError) -> bool {
+        matches!(
+            result,
+            Err(Error::Sessions(error)) if error.as_ref() == expected.as_ref()
+        )
+    }
+
+    #[tokio::test]
+    async fn refresh_rotates_the_token() {
+        let fixture = fixture();
+        let (session, first) = fixture.login().await;
+
+        let (refreshed, second) = fixture.service.refresh(&first).await.unwrap();
+        assert_eq!(refreshed.id, session.id);
+        assert_ne!(second, first);
+
+        let (_, third) = fixture.service.refresh(&second).await.unwrap();
+        assert_ne!(third, second);
+
+        //Only the latest token of the family is still unused
+        let unused = fixture.family(session.id).await
+            .into_iter()
+            .filter(|token| token.used_at.is_none())
+            .map(|token| token.token_hash)
+            .collect::<Vec<_>>();
+        assert_eq!(unused, [hash_token(&third)]);
+        assert_eq!(fixture.family(session.id).await.len(), 3);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn used_token_is_rejected() {
+        let fixture = fixture();
+        let (_, first) = fixture.login().await;
+
+        fixture.service.refresh(&first).await.unwrap();
+
+        assert!(is_error(
+            fixture.service.refresh(&first).await,
+            SessionError::RefreshTokenReused,
+        ));
+    }
+
+    #[tokio::test]
+    async fn reuse_ends_the_session_and_its_token_family() {
+        let fixture = fixture();
+        let (session, first) = fixture.login().await;
+        let (other_session, other_token) = fixture.login().await;
+
+        let (_, second) = fixture.service.refresh(&first).await.unwrap();
+        let (_, third) = fixture.service.refresh(&second).await.unwrap();
+
+        assert!(is_error(
+            fixture.service.refresh(&first).await,
+            SessionError::RefreshTokenReused,
+        ));
+
+        assert!(fixture.sessions.session_by_id(session.id).await.unwrap().is_none());
+        assert!(fixture.family(session.id).await.is_empty());
+
+        //Neither the owner's latest token nor a replay works anymore
+        assert!(is_error(
+            fixture.service.refresh(&third).await,
+            SessionError::RefreshTokenInvalid,
+        ));
+        assert!(is_error(
+            fixture.service.refresh(&first).await,
+            SessionError::RefreshTokenInvalid,
+        ));
+
+        //Other sessions of the user are left alone
+        assert!(fixture.sessions.session_by_id(other_session.id).await.unwrap().is_some());
+        fixture.service.refresh(&other_token).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn unknown_token_is_rejected() {
+        let fixture = fixture();
+        fixture.login().await;
+
+        assert!(is_error(
+            fixture.service.refresh(&generate_token()).await,
+            SessionError::RefreshTokenInvalid,
+        ));
+    }
+
+    #[tokio::test]
+    async fn token_of_expired_session_is_rejected() {
+        let fixture = fixture();
+        let (session, token) = fixture.login().await;
+
+        let expired_session = Session { expires_at: 0, ..session };
+        fixture.sessions.update_session(expired_session).await.unwrap();
+
+        assert!(is_error(
+            fixture.service.refresh(&token).await,
+            SessionError::RefreshTokenInvalid,
+        ));
+        assert_eq!(fixture.family(session.id).await.len(), 1);
+    }
+}
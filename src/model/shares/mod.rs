@@ -0,0 +1,3 @@
+pub mod shares_models;
+pub mod shares_repository;
+pub mod shares_service;
@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use strum_macros::AsRefStr;
+
+use crate::model::notes::notes_models::Note;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, AsRefStr)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum SharePermission {
+    Read,
+    Write,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, AsRefStr)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ShareAction {
+    Granted,
+    Changed,
+    Revoked,
+}
+
+/// Access to a note granted by its owner to another user.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NoteShare {
+    pub id: u64,
+    pub note_id: u64,
+    pub owner_id: u32,
+    pub user_id: u32,
+    pub permission: SharePermission,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct NewNoteShare {
+    pub note_id: u64,
+    pub owner_id: u32,
+    pub user_id: u32,
+    pub permission: SharePermission,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Audit record of a single sharing change. Events are never changed
+/// and outlive both the share and the note they are about.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShareEvent {
+    pub id: u64,
+    pub note_id: u64,
+    pub actor_id: u32,
+    pub user_id: u32,
+    pub action: ShareAction,
+    pub permission: SharePermission,
+    pub occurred_at: DateTime<Utc>,
+}
+
+pub struct NewShareEvent {
+    pub note_id: u64,
+    pub actor_id: u32,
+    pub user_id: u32,
+    pub action: ShareAction,
+    pub permission: SharePermission,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Grant of the note to the user with the nickname. Granting
+/// to a user who already has access changes the permission.
+#[derive(Deserialize)]
+pub struct NoteShareCreate {
+    pub nickname: String,
+    pub permission: SharePermission,
+}
+
+/// Share as listed to the owner of the note.
+#[derive(Serialize)]
+pub struct NoteShareDetails {
+    #[serde(flatten)]
+    pub share: NoteShare,
+    pub nickname: String,
+}
+
+/// Note of another user as listed to the one it is shared with.
+#[derive(Serialize)]
+pub struct SharedNote {
+    #[serde(flatten)]
+    pub note: Note,
+    pub permission: SharePermission,
+}
+
+impl SharePermission {
+    pub fn parse(permission: &str) -> Option<Self> {
+        match permission {
+            "read" => Some(SharePermission::Read),
+            "write" => Some(SharePermission::Write),
+            _ => None,
+        }
+    }
+}
+
+impl ShareAction {
+    pub fn parse(action: &str) -> Option<Self> {
+        match action {
+            "granted" => Some(ShareAction::Granted),
+            "changed" => Some(ShareAction::Changed),
+            "revoked" => Some(ShareAction::Revoked),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+
+use crate::error::StorageResult;
+use crate::model::shares::shares_models::{NewNoteShare, NewShareEvent, NoteShare, ShareEvent};
+
+#[async_trait]
+pub trait SharesRepository: Send + Sync {
+    /// Fails with `StorageError::Conflict` if the note is already
+    /// shared with the user.
+    async fn insert_share(&self, new_share: NewNoteShare) -> StorageResult<NoteShare>;
+
+    async fn share_by_id(&self, share_id: u64) -> StorageResult<Option<NoteShare>>;
+
+    async fn shares_by_note(&self, note_id: u64) -> StorageResult<Vec<NoteShare>>;
+
+    async fn shares_by_user(&self, user_id: u32) -> StorageResult<Vec<NoteShare>>;
+
+    async fn update_share(&self, share: NoteShare) -> StorageResult<Option<NoteShare>>;
+
+    async fn delete_share(&self, share_id: u64) -> StorageResult<Option<NoteShare>>;
+}
+
+#[async_trait]
+pub trait ShareEventsRepository: Send + Sync {
+    async fn insert_share_event(
+        &self, new_event: NewShareEvent,
+    ) -> StorageResult<ShareEvent>;
+
+    /// Events of the note, oldest first.
+    async fn share_events_by_note(&self, note_id: u64) -> StorageResult<Vec<ShareEvent>>;
+}
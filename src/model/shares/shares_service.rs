@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::error::{Error, NoteError, Result, UserError};
+use crate::model::notes::notes_models::Note;
+use crate::model::notes::notes_query::invalid_field;
+use crate::model::shares::shares_models::{
+    NewNoteShare,
+    NewShareEvent,
+    NoteShare,
+    NoteShareCreate,
+    NoteShareDetails,
+    ShareAction,
+    ShareEvent,
+    SharePermission,
+};
+use crate::model::shares::shares_repository::{ShareEventsRepository, SharesRepository};
+use crate::model::users::users_repository::UsersRepository;
+
+/// Storage side of note sharing, recording every change of a share
+/// as an event. Access checks are done by `NotesService`.
+#[derive(Clone)]
+pub struct SharesService {
+    repository: Arc<dyn SharesRepository>,
+    events: Arc<dyn ShareEventsRepository>,
+    users: Arc<dyn UsersRepository>,
+}
+
+impl SharesService {
+    pub fn new(
+        repository: Arc<dyn SharesRepository>,
+        events: Arc<dyn ShareEventsRepository>,
+        users: Arc<dyn UsersRepository>,
+    ) -> Self {
+        Self { repository, events, users }
+    }
+}
+
+impl SharesService {
+    /// Grants the note to the user with the nickname, or changes
+    /// the permission of the existing share.
+    pub async fn share(
+        &self, note: &Note, actor_id: u32, share_create: NoteShareCreate,
+    ) -> Result<NoteShareDetails> {
+        let user = self.users.user_by_nickname(&share_create.nickname).await
+            .map_err(|_| Error::Notes(NoteError::ShareFail))?
+            .ok_or(Error::User(UserError::UserDoesNotExists))?;
+
+        if user.id == note.creator_id {
+            return Err(invalid_field("nickname", "Note can not be shared with its owner"));
+        }
+
+        let existing_share = self.shares(note.id).await?
+            .into_iter()
+            .find(|share| share.user_id == user.id);
+
+        let (share, action) = match existing_share {
+            Some(share) if share.permission == share_create.permission => {
+                return Ok(NoteShareDetails { share, nickname: user.nickname });
+            }
+            Some(share) => {
+                let share = NoteShare { permission: share_create.permission, ..share };
+
+                let share = self.repository.update_share(share).await
+                    .map_err(|_| Error::Notes(NoteError::ShareFail))?
+                    .ok_or(Error::Notes(NoteError::ShareDoesNotExists))?;
+
+                (share, ShareAction::Changed)
+            }
+            None => {
+                let new_share = NewNoteShare {
+                    note_id: note.id,
+                    owner_id: note.creator_id,
+                    user_id: user.id,
+                    permission: share_create.permission,
+                    created_at: Utc::now(),
+                };
+
+                let share = self.repository.insert_share(new_share).await
+                    .map_err(|_| Error::Notes(NoteError::ShareFail))?;
+
+                (share, ShareAction::Granted)
+            }
+        };
+
+        self.record(&share, actor_id, action).await?;
+
+        Ok(NoteShareDetails { share, nickname: user.nickname })
+    }
+
+    /// Permission the note is shared with the user, if any.
+    pub async fn permission(&self, note_id: u64, user_id: u32) -> Result<Option<SharePermission>> {
+        let permission = self.shares(note_id).await?
+            .into_iter()
+            .find(|share| share.user_id == user_id)
+            .map(|share| share.permission);
+
+        Ok(permission)
+    }
+
+    pub async fn shares(&self, note_id: u64) -> Result<Vec<NoteShare>> {
+        self.repository.shares_by_note(note_id).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))
+    }
+
+    /// Shares of the note with the nicknames of the users,
+    /// leaving out users who have been deleted since.
+    pub async fn shares_details(&self, note_id: u64) -> Result<Vec<NoteShareDetails>> {
+        let mut shares_details = Vec::new();
+
+        for share in self.shares(note_id).await? {
+            let user = self.users.user_by_id(share.user_id).await
+                .map_err(|_| Error::Notes(NoteError::ReceiveFail))?;
+
+            if let Some(user) = user {
+                shares_details.push(NoteShareDetails { share, nickname: user.nickname });
+            }
+        }
+
+        Ok(shares_details)
+    }
+
+    pub async fn shared_with(&self, user_id: u32) -> Result<Vec<NoteShare>> {
+        self.repository.shares_by_user(user_id).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))
+    }
+
+    pub async fn revoke(
+        &self, note_id: u64, share_id: u64, actor_id: u32,
+    ) -> Result<NoteShare> {
+        self.repository.share_by_id(share_id).await
+            .map_err(|_| Error::Notes(NoteError::ShareFail))?
+            .filter(|share| share.note_id == note_id)
+            .ok_or(Error::Notes(NoteError::ShareDoesNotExists))?;
+
+        let share = self.repository.delete_share(share_id).await
+            .map_err(|_| Error::Notes(NoteError::ShareFail))?
+            .ok_or(Error::Notes(NoteError::ShareDoesNotExists))?;
+
+        self.record(&share, actor_id, ShareAction::Revoked).await?;
+
+        Ok(share)
+    }
+
+    pub async fn revoke_all(&self, note_id: u64, actor_id: u32) -> Result<()> {
+        for share in self.shares(note_id).await? {
+            self.revoke(note_id, share.id, actor_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sharing history of the note, oldest first.
+    pub async fn events(&self, note_id: u64) -> Result<Vec<ShareEvent>> {
+        self.events.share_events_by_note(note_id).await
+            .map_err(|_| Error::Notes(NoteError::ReceiveFail))
+    }
+
+    async fn record(&self, share: &NoteShare, actor_id: u32, action: ShareAction) -> Result<()> {
+        let new_event = NewShareEvent {
+            note_id: share.note_id,
+            actor_id,
+            user_id: share.user_id,
+            action,
+            permission: share.permission,
+            occurred_at: Utc::now(),
+        };
+
+        self.events.insert_share_event(new_event).await
+            .map_err(|_| Error::Notes(NoteError::ShareFail))?;
+
+        Ok(())
+    }
+}
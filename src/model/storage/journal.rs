@@ -0,0 +1,1069 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::error::{StorageError, StorageResult};
+use crate::log::log_layer;
+use crate::model::links::links_models::{NewNoteLink, NoteLink};
+use crate::model::links::links_repository::LinksRepository;
+use crate::model::notebooks::notebooks_models::{NewNotebook, Notebook};
+use crate::model::notebooks::notebooks_repository::NotebooksRepository;
+use crate::model::notes::notes_models::{NewNote, Note};
+use crate::model::notes::notes_query::{NotesFilter, NotesSlice};
+use crate::model::notes::notes_repository::NotesRepository;
+use crate::model::revisions::revisions_models::{NewNoteRevision, NoteRevision};
+use crate::model::revisions::revisions_repository::RevisionsRepository;
+use crate::model::sessions::sessions_models::{
+    NewRefreshToken,
+    NewSession,
+    RefreshToken,
+    Session,
+};
+use crate::model::sessions::sessions_repository::{RefreshTokensRepository, SessionsRepository};
+use crate::model::shares::shares_models::{NewNoteShare, NewShareEvent, NoteShare, ShareEvent};
+use crate::model::shares::shares_repository::{ShareEventsRepository, SharesRepository};
+use crate::model::storage::memory::{
+    InMemoryLinksRepository,
+    InMemoryNotebooksRepository,
+    InMemoryNotesRepository,
+    InMemoryRefreshTokensRepository,
+    InMemoryRevisionsRepository,
+    InMemorySessionsRepository,
+    InMemoryShareEventsRepository,
+    InMemorySharesRepository,
+    InMemoryUsersRepository,
+    Restorable,
+};
+use crate::model::users::users_models::{NewUser, User, UserRole};
+use crate::model::users::users_repository::UsersRepository;
+use crate::settings::FsyncPolicy;
+
+const JOURNAL_LAYER: &str = "JOURNAL";
+
+const JOURNAL_FILE: &str = "journal.jsonl";
+const SNAPSHOT_FILE: &str = "snapshot.json";
+const SNAPSHOT_TEMPORARY_FILE: &str = "snapshot.json.tmp";
+
+/// Single mutation, stored as one JSON line. Entries carry whole rows,
+/// so replaying an entry twice yields the same state.
+#[derive(Serialize, Deserialize)]
+enum JournalEntry {
+    PutUser(UserRecord),
+    DeleteUser(u32),
+    PutNote(Note),
+    DeleteNote(u64),
+    PutNotebook(Notebook),
+    DeleteNotebook(u64),
+    PutRevision(NoteRevision),
+    DeleteRevision(u64),
+    PutSession(Session),
+    DeleteSession(u32),
+    PutShare(NoteShare),
+    DeleteShare(u64),
+    PutShareEvent(ShareEvent),
+    PutLink(NoteLink),
+    DeleteLink(u64),
+    PutRefreshToken(RefreshToken),
+    DeleteRefreshToken(u64),
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Snapshot {
+    users: Vec<Option<UserRecord>>,
+    notes: Vec<Option<Note>>,
+    #[serde(default)]
+    notebooks: Vec<Option<Notebook>>,
+    #[serde(default)]
+    revisions: Vec<Option<NoteRevision>>,
+    sessions: Vec<Option<Session>>,
+    #[serde(default)]
+    shares: Vec<Option<NoteShare>>,
+    #[serde(default)]
+    share_events: Vec<Option<ShareEvent>>,
+    #[serde(default)]
+    links: Vec<Option<NoteLink>>,
+    #[serde(default)]
+    refresh_tokens: Vec<Option<RefreshToken>>,
+}
+
+/// Persisted form of `User`, which itself is never serialized.
+#[derive(Serialize, Deserialize)]
+struct UserRecord {
+    id: u32,
+    name: String,
+    nickname: String,
+    password: String,
+    #[serde(default)]
+    role: UserRole,
+    #[serde(default)]
+    created_at: DateTime<Utc>,
+}
+
+impl From<User> for UserRecord {
+    fn from(user: User) -> Self {
+        UserRecord {
+            id: user.id,
+            name: user.name,
+            nickname: user.nickname,
+            password: user.password,
+            role: user.role,
+            created_at: user.created_at,
+        }
+    }
+}
+
+impl From<UserRecord> for User {
+    fn from(record: UserRecord) -> Self {
+        User {
+            id: record.id,
+            name: record.name,
+            nickname: record.nickname,
+            password: record.password,
+            role: record.role,
+            created_at: record.created_at,
+        }
+    }
+}
+
+struct Journal {
+    file: File,
+    directory: PathBuf,
+    fsync: FsyncPolicy,
+    snapshot_every: u64,
+    //Bytes of complete entries in the journal file
+    length: u64,
+    entries_since_snapshot: u64,
+    unsynced: bool,
+}
+
+/// In-memory repositories made durable by an append-only journal
+/// of mutations, periodically compacted into a snapshot.
+pub struct JournalStorage {
+    users: InMemoryUsersRepository,
+    notes: InMemoryNotesRepository,
+    notebooks: InMemoryNotebooksRepository,
+    revisions: InMemoryRevisionsRepository,
+    sessions: InMemorySessionsRepository,
+    shares: InMemorySharesRepository,
+    share_events: InMemoryShareEventsRepository,
+    links: InMemoryLinksRepository,
+    refresh_tokens: InMemoryRefreshTokensRepository,
+    journal: Arc<Mutex<Journal>>,
+}
+
+impl JournalStorage {
+    pub fn open(
+        directory: &str, fsync: FsyncPolicy, snapshot_every: u64,
+    ) -> io::Result<Self> {
+        let directory = PathBuf::from(directory);
+        fs::create_dir_all(&directory)?;
+
+        let storage = Self {
+            users: InMemoryUsersRepository::default(),
+            notes: InMemoryNotesRepository::default(),
+            notebooks: InMemoryNotebooksRepository::default(),
+            revisions: InMemoryRevisionsRepository::default(),
+            sessions: InMemorySessionsRepository::default(),
+            shares: InMemorySharesRepository::default(),
+            share_events: InMemoryShareEventsRepository::default(),
+            links: InMemoryLinksRepository::default(),
+            refresh_tokens: InMemoryRefreshTokensRepository::default(),
+            journal: Arc::new(Mutex::new(Journal {
+                file: OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(directory.join(JOURNAL_FILE))?,
+                directory: directory.clone(),
+                fsync: fsync.clone(),
+                snapshot_every,
+                length: 0,
+                entries_since_snapshot: 0,
+                unsynced: false,
+            })),
+        };
+
+        let (replayed_entries, length) = storage.replay(&directory)?;
+
+        {
+            let mut journal = storage.journal.try_lock()
+                .expect("Journal is not shared yet");
+
+            // Drops a torn last entry, so the next one starts on a line of its own
+            journal.file.set_len(length)?;
+            journal.file.sync_data()?;
+            journal.length = length;
+            journal.entries_since_snapshot = replayed_entries;
+        }
+
+        log_layer(
+            JOURNAL_LAYER,
+            format!("replayed {replayed_entries} entries").as_str(),
+        );
+
+        if let FsyncPolicy::Interval(seconds) = fsync {
+            tokio::spawn(sync_periodically(
+                storage.journal.clone(),
+                Duration::from_secs(seconds),
+            ));
+        }
+
+        Ok(storage)
+    }
+
+    /// Restores the snapshot and the journal, returning the number of
+    /// replayed entries and the length of the journal they take up.
+    fn replay(&self, directory: &Path) -> io::Result<(u64, u64)> {
+        let snapshot_path = directory.join(SNAPSHOT_FILE);
+
+        if snapshot_path.exists() {
+            let snapshot: Snapshot = serde_json::from_reader(
+                BufReader::new(File::open(snapshot_path)?)
+            )?;
+
+            self.restore_snapshot(snapshot).map_err(io_error)?;
+        }
+
+        let journal = fs::read(directory.join(JOURNAL_FILE))?;
+
+        let mut replayed_entries = 0;
+        let mut length = 0;
+
+        for line in journal.split_inclusive(|byte| *byte == b'\n') {
+            let is_last = length + line.len() == journal.len();
+
+            let entry = line.strip_suffix(b"\n")
+                .and_then(|line| serde_json::from_slice::<JournalEntry>(line).ok());
+
+            let Some(entry) = entry else {
+                // A torn last line is left by a crash in the middle of an append,
+                // anything else means the journal is damaged
+                if is_last {
+                    log_layer(JOURNAL_LAYER, "dropping torn last entry");
+                    break;
+                }
+
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("corrupt journal entry at byte {length}"),
+                ));
+            };
+
+            self.apply(entry).map_err(io_error)?;
+            replayed_entries += 1;
+            length += line.len();
+        }
+
+        Ok((replayed_entries, length as u64))
+    }
+
+    fn restore_snapshot(&self, snapshot: Snapshot) -> StorageResult<()> {
+        for (id, user) in snapshot.users.into_iter().enumerate() {
+            self.users.restore_row(id, user.map(User::from))?;
+        }
+        for (id, note) in snapshot.notes.into_iter().enumerate() {
+            self.notes.restore_row(id, note)?;
+        }
+        for (id, notebook) in snapshot.notebooks.into_iter().enumerate() {
+            self.notebooks.restore_row(id, notebook)?;
+        }
+        for (id, revision) in snapshot.revisions.into_iter().enumerate() {
+            self.revisions.restore_row(id, revision)?;
+        }
+        for (id, session) in snapshot.sessions.into_iter().enumerate() {
+            self.sessions.restore_row(id, session)?;
+        }
+        for (id, share) in snapshot.shares.into_iter().enumerate() {
+            self.shares.restore_row(id, share)?;
+        }
+        for (id, event) in snapshot.share_events.into_iter().enumerate() {
+            self.share_events.restore_row(id, event)?;
+        }
+        for (id, link) in snapshot.links.into_iter().enumerate() {
+            self.links.restore_row(id, link)?;
+        }
+        for (id, refresh_token) in snapshot.refresh_tokens.into_iter().enumerate() {
+            self.refresh_tokens.restore_row(id, refresh_token)?;
+        }
+
+        Ok(())
+    }
+
+    fn apply(&self, entry: JournalEntry) -> StorageResult<()> {
+        match entry {
+            JournalEntry::PutUser(user) =>
+                self.users.restore_row(user.id as usize, Some(user.into())),
+            JournalEntry::DeleteUser(id) =>
+                self.users.restore_row(id as usize, None),
+            JournalEntry::PutNote(note) =>
+                self.notes.restore_row(note.id as usize, Some(note)),
+            JournalEntry::DeleteNote(id) =>
+                self.notes.restore_row(id as usize, None),
+            JournalEntry::PutNotebook(notebook) =>
+                self.notebooks.restore_row(notebook.id as usize, Some(notebook)),
+            JournalEntry::DeleteNotebook(id) =>
+                self.notebooks.restore_row(id as usize, None),
+            JournalEntry::PutRevision(revision) =>
+                self.revisions.restore_row(revision.id as usize, Some(revision)),
+            JournalEntry::DeleteRevision(id) =>
+                self.revisions.restore_row(id as usize, None),
+            JournalEntry::PutSession(session) =>
+                self.sessions.restore_row(session.id as usize, Some(session)),
+            JournalEntry::DeleteSession(id) =>
+                self.sessions.restore_row(id as usize, None),
+            JournalEntry::PutShare(share) =>
+                self.shares.restore_row(share.id as usize, Some(share)),
+            JournalEntry::DeleteShare(id) =>
+                self.shares.restore_row(id as usize, None),
+            JournalEntry::PutShareEvent(event) =>
+                self.share_events.restore_row(event.id as usize, Some(event)),
+            JournalEntry::PutLink(link) =>
+                self.links.restore_row(link.id as usize, Some(link)),
+            JournalEntry::DeleteLink(id) =>
+                self.links.restore_row(id as usize, None),
+            JournalEntry::PutRefreshToken(refresh_token) => self.refresh_tokens
+                .restore_row(refresh_token.id as usize, Some(refresh_token)),
+            JournalEntry::DeleteRefreshToken(id) =>
+                self.refresh_tokens.restore_row(id as usize, None),
+        }
+    }
+
+    fn snapshot(&self) -> StorageResult<Snapshot> {
+        Ok(Snapshot {
+            users: self.users.rows()?
+                .into_iter()
+                .map(|user| user.map(UserRecord::from))
+                .collect(),
+            notes: self.notes.rows()?,
+            notebooks: self.notebooks.rows()?,
+            revisions: self.revisions.rows()?,
+            sessions: self.sessions.rows()?,
+            shares: self.shares.rows()?,
+            share_events: self.share_events.rows()?,
+            links: self.links.rows()?,
+            refresh_tokens: self.refresh_tokens.rows()?,
+        })
+    }
+
+    /// Keeps a mutation already applied in memory only once its entry
+    /// is in the journal. When the entry can not be written, the row is
+    /// put back the way it was, so memory never holds what disk does not.
+    fn commit<R: Restorable>(
+        &self,
+        journal: &mut Journal,
+        repository: &R,
+        id: usize,
+        previous: Option<R::Row>,
+        entry: JournalEntry,
+    ) -> StorageResult<()> {
+        if let Err(error) = self.append(journal, entry) {
+            repository.restore_row(id, previous)?;
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    /// Appends the entry to the journal, compacting it into a snapshot
+    /// once enough entries have accumulated. Must be called while holding
+    /// the journal lock taken before the in-memory mutation, so entries
+    /// are written in the order the mutations were applied.
+    fn append(&self, journal: &mut Journal, entry: JournalEntry) -> StorageResult<()> {
+        let mut line = serde_json::to_vec(&entry)
+            .map_err(|_| StorageError::Unavailable)?;
+        line.push(b'\n');
+
+        journal.write(&line).map_err(io_storage_error)?;
+        journal.entries_since_snapshot += 1;
+
+        // The entry is durable by now, a failed compaction is retried on the next append
+        if journal.entries_since_snapshot >= journal.snapshot_every {
+            let compaction = self.snapshot()
+                .map_err(io_error)
+                .and_then(|snapshot| journal.compact(&snapshot));
+
+            if let Err(error) = compaction {
+                log_layer(JOURNAL_LAYER, format!("compaction failed: {error}").as_str());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Journal {
+    /// Writes a whole entry or nothing: a partly written or, under
+    /// the always policy, unsynced entry is cut off the file again.
+    fn write(&mut self, line: &[u8]) -> io::Result<()> {
+        let written = self.file.write_all(line).and_then(|_| {
+            self.unsynced = true;
+
+            match self.fsync {
+                FsyncPolicy::Always => self.sync(),
+                _ => Ok(()),
+            }
+        });
+
+        if let Err(error) = written {
+            self.file.set_len(self.length)?;
+            return Err(error);
+        }
+
+        self.length += line.len() as u64;
+
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_data()?;
+        self.unsynced = false;
+
+        Ok(())
+    }
+
+    /// Atomically replaces the snapshot and truncates the journal.
+    /// A crash between the two steps only leaves entries that are
+    /// already contained in the snapshot, which replay harmlessly.
+    fn compact(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        let temporary_path = self.directory.join(SNAPSHOT_TEMPORARY_FILE);
+
+        let mut temporary_file = File::create(&temporary_path)?;
+        serde_json::to_writer(&mut temporary_file, snapshot)?;
+        temporary_file.sync_all()?;
+
+        fs::rename(temporary_path, self.directory.join(SNAPSHOT_FILE))?;
+        File::open(&self.directory)?.sync_all()?;
+
+        self.file.set_len(0)?;
+        self.sync()?;
+        self.length = 0;
+        self.entries_since_snapshot = 0;
+
+        log_layer(JOURNAL_LAYER, "snapshot written");
+
+        Ok(())
+    }
+}
+
+async fn sync_periodically(journal: Arc<Mutex<Journal>>, interval: Duration) {
+    let mut interval = tokio::time::interval(interval);
+
+    loop {
+        interval.tick().await;
+
+        let mut journal = journal.lock().await;
+        if journal.unsynced {
+            if let Err(error) = journal.sync() {
+                log_layer(JOURNAL_LAYER, format!("sync failed: {error}").as_str());
+            }
+        }
+    }
+}
+
+fn io_error(error: StorageError) -> io::Error {
+    io::Error::other(format!("{error:?}"))
+}
+
+fn io_storage_error(error: io::Error) -> StorageError {
+    log_layer(JOURNAL_LAYER, format!("write failed: {error}").as_str());
+
+    StorageError::Unavailable
+}
+
+#[async_trait]
+impl UsersRepository for JournalStorage {
+    async fn insert_user(&self, new_user: NewUser) -> StorageResult<User> {
+        let mut journal = self.journal.lock().await;
+        let user = self.users.insert_user(new_user).await?;
+        self.commit(
+            &mut journal, &self.users, user.id as usize, None,
+            JournalEntry::PutUser(user.clone().into()),
+        )?;
+
+        Ok(user)
+    }
+
+    async fn user_by_id(&self, user_id: u32) -> StorageResult<Option<User>> {
+        self.users.user_by_id(user_id).await
+    }
+
+    async fn user_by_nickname(
+        &self, nickname: &str,
+    ) -> StorageResult<Option<User>> {
+        self.users.user_by_nickname(nickname).await
+    }
+
+    async fn update_user(&self, user: User) -> StorageResult<Option<User>> {
+        let mut journal = self.journal.lock().await;
+        let previous = self.users.row(user.id as usize)?;
+        let user = self.users.update_user(user).await?;
+        if let Some(user) = user.as_ref() {
+            self.commit(
+                &mut journal, &self.users, user.id as usize, previous,
+                JournalEntry::PutUser(user.clone().into()),
+            )?;
+        }
+
+        Ok(user)
+    }
+
+    async fn delete_user(&self, user_id: u32) -> StorageResult<Option<User>> {
+        let mut journal = self.journal.lock().await;
+        let user = self.users.delete_user(user_id).await?;
+        if user.is_some() {
+            self.commit(
+                &mut journal, &self.users, user_id as usize, user.clone(),
+                JournalEntry::DeleteUser(user_id),
+            )?;
+        }
+
+        Ok(user)
+    }
+}
+
+#[async_trait]
+impl NotesRepository for JournalStorage {
+    async fn insert_note(&self, new_note: NewNote) -> StorageResult<Note> {
+        let mut journal = self.journal.lock().await;
+        let note = self.notes.insert_note(new_note).await?;
+        self.commit(
+            &mut journal, &self.notes, note.id as usize, None,
+            JournalEntry::PutNote(note.clone()),
+        )?;
+
+        Ok(note)
+    }
+
+    async fn note_by_id(&self, note_id: u64) -> StorageResult<Option<Note>> {
+        self.notes.note_by_id(note_id).await
+    }
+
+    async fn notes_by_creator(&self, creator_id: u32) -> StorageResult<Vec<Note>> {
+        self.notes.notes_by_creator(creator_id).await
+    }
+
+    async fn notes_by_filter(&self, filter: &NotesFilter) -> StorageResult<NotesSlice> {
+        self.notes.notes_by_filter(filter).await
+    }
+
+    async fn all_notes(&self) -> StorageResult<Vec<Note>> {
+        self.notes.all_notes().await
+    }
+
+    async fn update_note(&self, note: Note) -> StorageResult<Option<Note>> {
+        let mut journal = self.journal.lock().await;
+        let previous = self.notes.row(note.id as usize)?;
+        let note = self.notes.update_note(note).await?;
+        if let Some(note) = note.as_ref() {
+            self.commit(
+                &mut journal, &self.notes, note.id as usize, previous,
+                JournalEntry::PutNote(note.clone()),
+            )?;
+        }
+
+        Ok(note)
+    }
+
+    async fn delete_note(&self, note_id: u64) -> StorageResult<Option<Note>> {
+        let mut journal = self.journal.lock().await;
+        let note = self.notes.delete_note(note_id).await?;
+        if note.is_some() {
+            self.commit(
+                &mut journal, &self.notes, note_id as usize, note.clone(),
+                JournalEntry::DeleteNote(note_id),
+            )?;
+        }
+
+        Ok(note)
+    }
+}
+
+#[async_trait]
+impl NotebooksRepository for JournalStorage {
+    async fn insert_notebook(
+        &self, new_notebook: NewNotebook,
+    ) -> StorageResult<Notebook> {
+        let mut journal = self.journal.lock().await;
+        let notebook = self.notebooks.insert_notebook(new_notebook).await?;
+        self.commit(
+            &mut journal, &self.notebooks, notebook.id as usize, None,
+            JournalEntry::PutNotebook(notebook.clone()),
+        )?;
+
+        Ok(notebook)
+    }
+
+    async fn notebook_by_id(
+        &self, notebook_id: u64,
+    ) -> StorageResult<Option<Notebook>> {
+        self.notebooks.notebook_by_id(notebook_id).await
+    }
+
+    async fn notebooks_by_owner(&self, owner_id: u32) -> StorageResult<Vec<Notebook>> {
+        self.notebooks.notebooks_by_owner(owner_id).await
+    }
+
+    async fn update_notebook(
+        &self, notebook: Notebook,
+    ) -> StorageResult<Option<Notebook>> {
+        let mut journal = self.journal.lock().await;
+        let previous = self.notebooks.row(notebook.id as usize)?;
+        let notebook = self.notebooks.update_notebook(notebook).await?;
+        if let Some(notebook) = notebook.as_ref() {
+            self.commit(
+                &mut journal, &self.notebooks, notebook.id as usize, previous,
+                JournalEntry::PutNotebook(notebook.clone()),
+            )?;
+        }
+
+        Ok(notebook)
+    }
+
+    async fn delete_notebook(
+        &self, notebook_id: u64,
+    ) -> StorageResult<Option<Notebook>> {
+        let mut journal = self.journal.lock().await;
+        let notebook = self.notebooks.delete_notebook(notebook_id).await?;
+        if notebook.is_some() {
+            self.commit(
+                &mut journal, &self.notebooks, notebook_id as usize, notebook.clone(),
+                JournalEntry::DeleteNotebook(notebook_id),
+            )?;
+        }
+
+        Ok(notebook)
+    }
+}
+
+#[async_trait]
+impl RevisionsRepository for JournalStorage {
+    async fn insert_revision(
+        &self, new_revision: NewNoteRevision,
+    ) -> StorageResult<NoteRevision> {
+        let mut journal = self.journal.lock().await;
+        let revision = self.revisions.insert_revision(new_revision).await?;
+        self.commit(
+            &mut journal, &self.revisions, revision.id as usize, None,
+            JournalEntry::PutRevision(revision.clone()),
+        )?;
+
+        Ok(revision)
+    }
+
+    async fn revision_by_id(
+        &self, revision_id: u64,
+    ) -> StorageResult<Option<NoteRevision>> {
+        self.revisions.revision_by_id(revision_id).await
+    }
+
+    async fn revisions_by_note(&self, note_id: u64) -> StorageResult<Vec<NoteRevision>> {
+        self.revisions.revisions_by_note(note_id).await
+    }
+
+    async fn delete_revision(
+        &self, revision_id: u64,
+    ) -> StorageResult<Option<NoteRevision>> {
+        let mut journal = self.journal.lock().await;
+        let revision = self.revisions.delete_revision(revision_id).await?;
+        if revision.is_some() {
+            self.commit(
+                &mut journal, &self.revisions, revision_id as usize, revision.clone(),
+                JournalEntry::DeleteRevision(revision_id),
+            )?;
+        }
+
+        Ok(revision)
+    }
+}
+
+#[async_trait]
+impl SessionsRepository for JournalStorage {
+    async fn insert_session(
+        &self, new_session: NewSession,
+    ) -> StorageResult<Session> {
+        let mut journal = self.journal.lock().await;
+        let session = self.sessions.insert_session(new_session).await?;
+        self.commit(
+            &mut journal, &self.sessions, session.id as usize, None,
+            JournalEntry::PutSession(session.clone()),
+        )?;
+
+        Ok(session)
+    }
+
+    async fn session_by_id(
+        &self, session_id: u32,
+    ) -> StorageResult<Option<Session>> {
+        self.sessions.session_by_id(session_id).await
+    }
+
+    async fn sessions_by_user(&self, user_id: u32) -> StorageResult<Vec<Session>> {
+        self.sessions.sessions_by_user(user_id).await
+    }
+
+    async fn update_session(
+        &self, session: Session,
+    ) -> StorageResult<Option<Session>> {
+        let mut journal = self.journal.lock().await;
+        let previous = self.sessions.row(session.id as usize)?;
+        let session = self.sessions.update_session(session).await?;
+        if let Some(session) = session.as_ref() {
+            self.commit(
+                &mut journal, &self.sessions, session.id as usize, previous,
+                JournalEntry::PutSession(session.clone()),
+            )?;
+        }
+
+        Ok(session)
+    }
+
+    async fn delete_session(
+        &self, session_id: u32,
+    ) -> StorageResult<Option<Session>> {
+        let mut journal = self.journal.lock().await;
+        let session = self.sessions.delete_session(session_id).await?;
+        if session.is_some() {
+            self.commit(
+                &mut journal, &self.sessions, session_id as usize, session.clone(),
+                JournalEntry::DeleteSession(session_id),
+            )?;
+        }
+
+        Ok(session)
+    }
+}
+
+#[async_trait]
+impl SharesRepository for JournalStorage {
+    async fn insert_share(&self, new_share: NewNoteShare) -> StorageResult<NoteShare> {
+        let mut journal = self.journal.lock().await;
+        let share = self.shares.insert_share(new_share).await?;
+        self.commit(
+            &mut journal, &self.shares, share.id as usize, None,
+            JournalEntry::PutShare(share.clone()),
+        )?;
+
+        Ok(share)
+    }
+
+    async fn share_by_id(&self, share_id: u64) -> StorageResult<Option<NoteShare>> {
+        self.shares.share_by_id(share_id).await
+    }
+
+    async fn shares_by_note(&self, note_id: u64) -> StorageResult<Vec<NoteShare>> {
+        self.shares.shares_by_note(note_id).await
+    }
+
+    async fn shares_by_user(&self, user_id: u32) -> StorageResult<Vec<NoteShare>> {
+        self.shares.shares_by_user(user_id).await
+    }
+
+    async fn update_share(&self, share: NoteShare) -> StorageResult<Option<NoteShare>> {
+        let mut journal = self.journal.lock().await;
+        let previous = self.shares.row(share.id as usize)?;
+        let share = self.shares.update_share(share).await?;
+        if let Some(share) = share.as_ref() {
+            self.commit(
+                &mut journal, &self.shares, share.id as usize, previous,
+                JournalEntry::PutShare(share.clone()),
+            )?;
+        }
+
+        Ok(share)
+    }
+
+    async fn delete_share(&self, share_id: u64) -> StorageResult<Option<NoteShare>> {
+        let mut journal = self.journal.lock().await;
+        let share = self.shares.delete_share(share_id).await?;
+        if share.is_some() {
+            self.commit(
+                &mut journal, &self.shares, share_id as usize, share.clone(),
+                JournalEntry::DeleteShare(share_id),
+            )?;
+        }
+
+        Ok(share)
+    }
+}
+
+#[async_trait]
+impl ShareEventsRepository for JournalStorage {
+    async fn insert_share_event(
+        &self, new_event: NewShareEvent,
+    ) -> StorageResult<ShareEvent> {
+        let mut journal = self.journal.lock().await;
+        let event = self.share_events.insert_share_event(new_event).await?;
+        self.commit(
+            &mut journal, &self.share_events, event.id as usize, None,
+            JournalEntry::PutShareEvent(event.clone()),
+        )?;
+
+        Ok(event)
+    }
+
+    async fn share_events_by_note(&self, note_id: u64) -> StorageResult<Vec<ShareEvent>> {
+        self.share_events.share_events_by_note(note_id).await
+    }
+}
+
+#[async_trait]
+impl LinksRepository for JournalStorage {
+    async fn insert_link(&self, new_link: NewNoteLink) -> StorageResult<NoteLink> {
+        let mut journal = self.journal.lock().await;
+        let link = self.links.insert_link(new_link).await?;
+        self.commit(
+            &mut journal, &self.links, link.id as usize, None,
+            JournalEntry::PutLink(link.clone()),
+        )?;
+
+        Ok(link)
+    }
+
+    async fn link_by_id(&self, link_id: u64) -> StorageResult<Option<NoteLink>> {
+        self.links.link_by_id(link_id).await
+    }
+
+    async fn link_by_token(&self, token: &str) -> StorageResult<Option<NoteLink>> {
+        self.links.link_by_token(token).await
+    }
+
+    async fn links_by_note(&self, note_id: u64) -> StorageResult<Vec<NoteLink>> {
+        self.links.links_by_note(note_id).await
+    }
+
+    async fn delete_link(&self, link_id: u64) -> StorageResult<Option<NoteLink>> {
+        let mut journal = self.journal.lock().await;
+        let link = self.links.delete_link(link_id).await?;
+        if link.is_some() {
+            self.commit(
+                &mut journal, &self.links, link_id as usize, link.clone(),
+                JournalEntry::DeleteLink(link_id),
+            )?;
+        }
+
+        Ok(link)
+    }
+}
+
+#[async_trait]
+impl RefreshTokensRepository for JournalStorage {
+    async fn insert_refresh_token(
+        &self, new_refresh_token: NewRefreshToken,
+    ) -> StorageResult<RefreshToken> {
+        let mut journal = self.journal.lock().await;
+        let refresh_token = self.refresh_tokens.insert_refresh_token(new_refresh_token).await?;
+        self.commit(
+            &mut journal, &self.refresh_tokens, refresh_token.id as usize, None,
+            JournalEntry::PutRefreshToken(refresh_token.clone()),
+        )?;
+
+        Ok(refresh_token)
+    }
+
+    async fn refresh_token_by_hash(
+        &self, token_hash: &str,
+    ) -> StorageResult<Option<RefreshToken>> {
+        self.refresh_tokens.refresh_token_by_hash(token_hash).await
+    }
+
+    async fn refresh_tokens_by_session(
+        &self, session_id: u32,
+    ) -> StorageResult<Vec<RefreshToken>> {
+        self.refresh_tokens.refresh_tokens_by_session(session_id).await
+    }
+
+    async fn use_refresh_token(
+        &self, refresh_token_id: u64, used_at: DateTime<Utc>,
+    ) -> StorageResult<Option<RefreshToken>> {
+        let mut journal = self.journal.lock().await;
+        let previous = self.refresh_tokens.row(refresh_token_id as usize)?;
+        let refresh_token = self.refresh_tokens
+            .use_refresh_token(refresh_token_id, used_at).await?;
+        if let Some(refresh_token) = refresh_token.as_ref() {
+            self.commit(
+                &mut journal, &self.refresh_tokens, refresh_token.id as usize, previous,
+                JournalEntry::PutRefreshToken(refresh_token.clone()),
+            )?;
+        }
+
+        Ok(refresh_token)
+    }
+
+    async fn delete_refresh_token(
+        &self, refresh_token_id: u64,
+    ) -> StorageResult<Option<RefreshToken>> {
+        let mut journal = self.journal.lock().await;
+        let refresh_token = self.refresh_tokens.delete_refresh_token(refresh_token_id).await?;
+        if refresh_token.is_some() {
+            self.commit(
+                &mut journal, &self.refresh_tokens, refresh_token_id as usize, refresh_token.clone(),
+                JournalEntry::DeleteRefreshToken(refresh_token_id),
+            )?;
+        }
+
+        Ok(refresh_token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Directory under the system temporary one, removed on drop.
+    struct TemporaryDirectory(PathBuf);
+
+    impl TemporaryDirectory {
+        fn new() -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("notes-journal-{}", uuid::Uuid::new_v4()));
+
+            Self(path)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+
+        fn journal(&self) -> Vec<u8> {
+            fs::read(self.0.join(JOURNAL_FILE)).unwrap()
+        }
+    }
+
+    impl Drop for TemporaryDirectory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn open(directory: &TemporaryDirectory, snapshot_every: u64) -> JournalStorage {
+        JournalStorage::open(directory.path(), FsyncPolicy::Always, snapshot_every).unwrap()
+    }
+
+    fn new_note(title: &str) -> NewNote {
+        NewNote {
+            creator_id: 1,
+            title: title.to_string(),
+            body: String::new(),
+            created_at: Utc::now(),
+            tags: vec![],
+            notebook_id: None,
+        }
+    }
+
+    async fn titles(storage: &JournalStorage) -> Vec<String> {
+        storage.notes_by_creator(1).await.unwrap()
+            .into_iter()
+            .map(|note| note.title)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn replays_snapshot_and_journal() {
+        let directory = TemporaryDirectory::new();
+
+        {
+            let storage = open(&directory, 3);
+            let first = storage.insert_note(new_note("first")).await.unwrap();
+            storage.insert_note(new_note("second")).await.unwrap();
+            storage.insert_note(new_note("third")).await.unwrap();
+            storage.insert_note(new_note("fourth")).await.unwrap();
+            storage.delete_note(first.id).await.unwrap();
+        }
+
+        //Three entries went into the snapshot, two are left in the journal
+        assert!(directory.0.join(SNAPSHOT_FILE).exists());
+        assert_eq!(directory.journal().split(|byte| *byte == b'\n').count() - 1, 2);
+
+        let storage = open(&directory, 3);
+        assert_eq!(titles(&storage).await, ["second", "third", "fourth"]);
+
+        let journal = storage.journal.lock().await;
+        assert_eq!(journal.entries_since_snapshot, 2);
+        assert_eq!(journal.length, directory.journal().len() as u64);
+    }
+
+    #[tokio::test]
+    async fn truncates_torn_last_entry() {
+        let directory = TemporaryDirectory::new();
+
+        {
+            let storage = open(&directory, 100);
+            storage.insert_note(new_note("kept")).await.unwrap();
+        }
+
+        let complete = directory.journal();
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(directory.0.join(JOURNAL_FILE))
+            .unwrap();
+        file.write_all(br#"{"PutNote":{"id":"#).unwrap();
+
+        {
+            let storage = open(&directory, 100);
+            assert_eq!(titles(&storage).await, ["kept"]);
+            assert_eq!(directory.journal(), complete);
+
+            storage.insert_note(new_note("appended")).await.unwrap();
+        }
+
+        //The entry after the cut starts on a line of its own
+        let storage = open(&directory, 100);
+        assert_eq!(titles(&storage).await, ["kept", "appended"]);
+    }
+
+    #[tokio::test]
+    async fn rejects_corrupt_entry_before_the_last() {
+        let directory = TemporaryDirectory::new();
+
+        {
+            let storage = open(&directory, 100);
+            storage.insert_note(new_note("kept")).await.unwrap();
+        }
+
+        let mut journal = b"{corrupt}\n".to_vec();
+        journal.extend(directory.journal());
+        fs::write(directory.0.join(JOURNAL_FILE), journal).unwrap();
+
+        let error = JournalStorage::open(directory.path(), FsyncPolicy::Always, 100)
+            .err()
+            .unwrap();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn rolls_back_failed_append() {
+        let directory = TemporaryDirectory::new();
+        let storage = open(&directory, 100);
+
+        let note = storage.insert_note(new_note("original")).await.unwrap();
+        let complete = directory.journal();
+
+        //Writes to a handle opened for reading only fail
+        storage.journal.lock().await.file = File::open(directory.0.join(JOURNAL_FILE)).unwrap();
+
+        let inserted = storage.insert_note(new_note("inserted")).await;
+        assert!(matches!(inserted, Err(StorageError::Unavailable)));
+
+        let updated = storage.update_note(Note {
+            title: "updated".to_string(),
+            version: note.version + 1,
+            ..note.clone()
+        }).await;
+        assert!(matches!(updated, Err(StorageError::Unavailable)));
+
+        let deleted = storage.delete_note(note.id).await;
+        assert!(matches!(deleted, Err(StorageError::Unavailable)));
+
+        assert_eq!(titles(&storage).await, ["original"]);
+        assert_eq!(storage.note_by_id(note.id).await.unwrap().unwrap().version, note.version);
+        assert_eq!(directory.journal(), complete);
+        assert_eq!(storage.journal.lock().await.length, complete.len() as u64);
+    }
+}
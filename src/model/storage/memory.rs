@@ -0,0 +1,963 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::error::{StorageError, StorageResult};
+use crate::model::links::links_models::{NewNoteLink, NoteLink};
+use crate::model::links::links_repository::LinksRepository;
+use crate::model::notebooks::notebooks_models::{NewNotebook, Notebook};
+use crate::model::notebooks::notebooks_repository::NotebooksRepository;
+use crate::model::notes::notes_models::{NewNote, Note, INITIAL_NOTE_VERSION};
+use crate::model::notes::notes_query::{NotesFilter, NotesSlice};
+use crate::model::notes::notes_repository::NotesRepository;
+use crate::model::revisions::revisions_models::{NewNoteRevision, NoteRevision};
+use crate::model::revisions::revisions_repository::RevisionsRepository;
+use crate::model::sessions::sessions_models::{
+    NewRefreshToken,
+    NewSession,
+    RefreshToken,
+    Session,
+};
+use crate::model::sessions::sessions_repository::{RefreshTokensRepository, SessionsRepository};
+use crate::model::shares::shares_models::{NewNoteShare, NewShareEvent, NoteShare, ShareEvent};
+use crate::model::shares::shares_repository::{ShareEventsRepository, SharesRepository};
+use crate::model::users::users_models::{NewUser, User};
+use crate::model::users::users_repository::UsersRepository;
+
+#[derive(Default)]
+pub struct InMemoryUsersRepository {
+    users_collection: Mutex<Vec<Option<User>>>,
+}
+
+#[derive(Default)]
+pub struct InMemoryNotesRepository {
+    notes_collection: Mutex<Vec<Option<Note>>>,
+}
+
+#[derive(Default)]
+pub struct InMemoryNotebooksRepository {
+    notebooks_collection: Mutex<Vec<Option<Notebook>>>,
+}
+
+#[derive(Default)]
+pub struct InMemoryRevisionsRepository {
+    revisions_collection: Mutex<Vec<Option<NoteRevision>>>,
+}
+
+#[derive(Default)]
+pub struct InMemorySharesRepository {
+    shares_collection: Mutex<Vec<Option<NoteShare>>>,
+}
+
+#[derive(Default)]
+pub struct InMemoryShareEventsRepository {
+    share_events_collection: Mutex<Vec<Option<ShareEvent>>>,
+}
+
+#[derive(Default)]
+pub struct InMemoryLinksRepository {
+    links_collection: Mutex<Vec<Option<NoteLink>>>,
+}
+
+#[derive(Default)]
+pub struct InMemorySessionsRepository {
+    sessions_collection: Mutex<Vec<Option<Session>>>,
+}
+
+#[derive(Default)]
+pub struct InMemoryRefreshTokensRepository {
+    refresh_tokens_collection: Mutex<Vec<Option<RefreshToken>>>,
+}
+
+#[async_trait]
+impl UsersRepository for InMemoryUsersRepository {
+    async fn insert_user(&self, new_user: NewUser) -> StorageResult<User> {
+        let mut collection = self.users_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let is_user_with_same_nickname_exists = collection.iter()
+            .any(|user_option| user_option.as_ref()
+                .is_some_and(|user| user.nickname == new_user.nickname)
+            );
+
+        if is_user_with_same_nickname_exists {
+            return Err(StorageError::Conflict);
+        }
+
+        let user = User {
+            id: collection.len() as u32,
+            name: new_user.name,
+            nickname: new_user.nickname,
+            password: new_user.password,
+            role: new_user.role,
+            created_at: new_user.created_at,
+        };
+
+        collection.push(Some(user.clone()));
+
+        Ok(user)
+    }
+
+    async fn user_by_id(&self, user_id: u32) -> StorageResult<Option<User>> {
+        let collection = self.users_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get(user_id as usize).cloned().flatten())
+    }
+
+    async fn user_by_nickname(
+        &self, nickname: &str,
+    ) -> StorageResult<Option<User>> {
+        let collection = self.users_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let user = collection.iter()
+            .flatten()
+            .find(|user| user.nickname == nickname)
+            .cloned();
+
+        Ok(user)
+    }
+
+    async fn update_user(&self, user: User) -> StorageResult<Option<User>> {
+        let mut collection = self.users_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let is_nickname_captured = collection.iter()
+            .flatten()
+            .any(|stored| stored.nickname == user.nickname && stored.id != user.id);
+
+        if is_nickname_captured {
+            return Err(StorageError::Conflict);
+        }
+
+        match collection.get_mut(user.id as usize) {
+            Some(slot @ Some(_)) => {
+                *slot = Some(user.clone());
+                Ok(Some(user))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn delete_user(&self, user_id: u32) -> StorageResult<Option<User>> {
+        let mut collection = self.users_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get_mut(user_id as usize).and_then(|user| user.take()))
+    }
+}
+
+#[async_trait]
+impl NotesRepository for InMemoryNotesRepository {
+    async fn insert_note(&self, new_note: NewNote) -> StorageResult<Note> {
+        let mut collection = self.notes_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let note = Note {
+            id: collection.len() as u64,
+            creator_id: new_note.creator_id,
+            title: new_note.title,
+            body: new_note.body,
+            created_at: new_note.created_at,
+            updated_at: new_note.created_at,
+            last_editor_id: new_note.creator_id,
+            tags: new_note.tags,
+            notebook_id: new_note.notebook_id,
+            version: INITIAL_NOTE_VERSION,
+            deleted_at: None,
+        };
+
+        collection.push(Some(note.clone()));
+
+        Ok(note)
+    }
+
+    async fn note_by_id(&self, note_id: u64) -> StorageResult<Option<Note>> {
+        let collection = self.notes_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get(note_id as usize).cloned().flatten())
+    }
+
+    async fn notes_by_creator(&self, creator_id: u32) -> StorageResult<Vec<Note>> {
+        let collection = self.notes_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let notes = collection.iter()
+            .flatten()
+            .filter(|note| note.creator_id == creator_id)
+            .cloned()
+            .collect();
+
+        Ok(notes)
+    }
+
+    async fn notes_by_filter(&self, filter: &NotesFilter) -> StorageResult<NotesSlice> {
+        let collection = self.notes_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let mut notes = collection.iter()
+            .flatten()
+            .filter(|note| filter.matches(note))
+            .collect::<Vec<_>>();
+
+        let total = notes.len();
+
+        notes.sort_by(|first, second| {
+            filter.compare(first, &filter.sort_key(second), second.id)
+        });
+
+        let notes = notes.into_iter()
+            .filter(|note| filter.is_after_cursor(note))
+            .take(filter.limit)
+            .cloned()
+            .collect();
+
+        Ok(NotesSlice { notes, total })
+    }
+
+    async fn all_notes(&self) -> StorageResult<Vec<Note>> {
+        let collection = self.notes_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.iter().flatten().cloned().collect())
+    }
+
+    async fn update_note(&self, note: Note) -> StorageResult<Option<Note>> {
+        let mut collection = self.notes_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        match collection.get_mut(note.id as usize) {
+            Some(Some(stored)) if stored.version + 1 != note.version =>
+                Err(StorageError::Conflict),
+            Some(slot @ Some(_)) => {
+                *slot = Some(note.clone());
+                Ok(Some(note))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn delete_note(&self, note_id: u64) -> StorageResult<Option<Note>> {
+        let mut collection = self.notes_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get_mut(note_id as usize).and_then(|note| note.take()))
+    }
+}
+
+#[async_trait]
+impl NotebooksRepository for InMemoryNotebooksRepository {
+    async fn insert_notebook(
+        &self, new_notebook: NewNotebook,
+    ) -> StorageResult<Notebook> {
+        let mut collection = self.notebooks_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let notebook = Notebook {
+            id: collection.len() as u64,
+            owner_id: new_notebook.owner_id,
+            parent_id: new_notebook.parent_id,
+            name: new_notebook.name,
+            position: new_notebook.position,
+            created_at: new_notebook.created_at,
+        };
+
+        collection.push(Some(notebook.clone()));
+
+        Ok(notebook)
+    }
+
+    async fn notebook_by_id(
+        &self, notebook_id: u64,
+    ) -> StorageResult<Option<Notebook>> {
+        let collection = self.notebooks_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get(notebook_id as usize).cloned().flatten())
+    }
+
+    async fn notebooks_by_owner(&self, owner_id: u32) -> StorageResult<Vec<Notebook>> {
+        let collection = self.notebooks_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let notebooks = collection.iter()
+            .flatten()
+            .filter(|notebook| notebook.owner_id == owner_id)
+            .cloned()
+            .collect();
+
+        Ok(notebooks)
+    }
+
+    async fn update_notebook(
+        &self, notebook: Notebook,
+    ) -> StorageResult<Option<Notebook>> {
+        let mut collection = self.notebooks_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        match collection.get_mut(notebook.id as usize) {
+            Some(slot @ Some(_)) => {
+                *slot = Some(notebook.clone());
+                Ok(Some(notebook))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn delete_notebook(
+        &self, notebook_id: u64,
+    ) -> StorageResult<Option<Notebook>> {
+        let mut collection = self.notebooks_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get_mut(notebook_id as usize).and_then(|notebook| notebook.take()))
+    }
+}
+
+#[async_trait]
+impl RevisionsRepository for InMemoryRevisionsRepository {
+    async fn insert_revision(
+        &self, new_revision: NewNoteRevision,
+    ) -> StorageResult<NoteRevision> {
+        let mut collection = self.revisions_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let revision = NoteRevision {
+            id: collection.len() as u64,
+            note_id: new_revision.note_id,
+            editor_id: new_revision.editor_id,
+            edited_at: new_revision.edited_at,
+            title: new_revision.title,
+            body: new_revision.body,
+        };
+
+        collection.push(Some(revision.clone()));
+
+        Ok(revision)
+    }
+
+    async fn revision_by_id(
+        &self, revision_id: u64,
+    ) -> StorageResult<Option<NoteRevision>> {
+        let collection = self.revisions_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get(revision_id as usize).cloned().flatten())
+    }
+
+    async fn revisions_by_note(&self, note_id: u64) -> StorageResult<Vec<NoteRevision>> {
+        let collection = self.revisions_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let revisions = collection.iter()
+            .flatten()
+            .filter(|revision| revision.note_id == note_id)
+            .cloned()
+            .collect();
+
+        Ok(revisions)
+    }
+
+    async fn delete_revision(
+        &self, revision_id: u64,
+    ) -> StorageResult<Option<NoteRevision>> {
+        let mut collection = self.revisions_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get_mut(revision_id as usize).and_then(|revision| revision.take()))
+    }
+}
+
+#[async_trait]
+impl SharesRepository for InMemorySharesRepository {
+    async fn insert_share(&self, new_share: NewNoteShare) -> StorageResult<NoteShare> {
+        let mut collection = self.shares_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let is_note_already_shared_with_user = collection.iter()
+            .flatten()
+            .any(|share| share.note_id == new_share.note_id
+                && share.user_id == new_share.user_id
+            );
+
+        if is_note_already_shared_with_user {
+            return Err(StorageError::Conflict);
+        }
+
+        let share = NoteShare {
+            id: collection.len() as u64,
+            note_id: new_share.note_id,
+            owner_id: new_share.owner_id,
+            user_id: new_share.user_id,
+            permission: new_share.permission,
+            created_at: new_share.created_at,
+        };
+
+        collection.push(Some(share.clone()));
+
+        Ok(share)
+    }
+
+    async fn share_by_id(&self, share_id: u64) -> StorageResult<Option<NoteShare>> {
+        let collection = self.shares_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get(share_id as usize).cloned().flatten())
+    }
+
+    async fn shares_by_note(&self, note_id: u64) -> StorageResult<Vec<NoteShare>> {
+        let collection = self.shares_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let shares = collection.iter()
+            .flatten()
+            .filter(|share| share.note_id == note_id)
+            .cloned()
+            .collect();
+
+        Ok(shares)
+    }
+
+    async fn shares_by_user(&self, user_id: u32) -> StorageResult<Vec<NoteShare>> {
+        let collection = self.shares_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let shares = collection.iter()
+            .flatten()
+            .filter(|share| share.user_id == user_id)
+            .cloned()
+            .collect();
+
+        Ok(shares)
+    }
+
+    async fn update_share(&self, share: NoteShare) -> StorageResult<Option<NoteShare>> {
+        let mut collection = self.shares_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        match collection.get_mut(share.id as usize) {
+            Some(slot @ Some(_)) => {
+                *slot = Some(share.clone());
+                Ok(Some(share))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn delete_share(&self, share_id: u64) -> StorageResult<Option<NoteShare>> {
+        let mut collection = self.shares_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get_mut(share_id as usize).and_then(|share| share.take()))
+    }
+}
+
+#[async_trait]
+impl ShareEventsRepository for InMemoryShareEventsRepository {
+    async fn insert_share_event(
+        &self, new_event: NewShareEvent,
+    ) -> StorageResult<ShareEvent> {
+        let mut collection = self.share_events_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let event = ShareEvent {
+            id: collection.len() as u64,
+            note_id: new_event.note_id,
+            actor_id: new_event.actor_id,
+            user_id: new_event.user_id,
+            action: new_event.action,
+            permission: new_event.permission,
+            occurred_at: new_event.occurred_at,
+        };
+
+        collection.push(Some(event.clone()));
+
+        Ok(event)
+    }
+
+    async fn share_events_by_note(&self, note_id: u64) -> StorageResult<Vec<ShareEvent>> {
+        let collection = self.share_events_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let events = collection.iter()
+            .flatten()
+            .filter(|event| event.note_id == note_id)
+            .cloned()
+            .collect();
+
+        Ok(events)
+    }
+}
+
+#[async_trait]
+impl LinksRepository for InMemoryLinksRepository {
+    async fn insert_link(&self, new_link: NewNoteLink) -> StorageResult<NoteLink> {
+        let mut collection = self.links_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let is_token_taken = collection.iter()
+            .flatten()
+            .any(|link| link.token == new_link.token);
+
+        if is_token_taken {
+            return Err(StorageError::Conflict);
+        }
+
+        let link = NoteLink {
+            id: collection.len() as u64,
+            note_id: new_link.note_id,
+            owner_id: new_link.owner_id,
+            token: new_link.token,
+            password_hash: new_link.password_hash,
+            expires_at: new_link.expires_at,
+            created_at: new_link.created_at,
+        };
+
+        collection.push(Some(link.clone()));
+
+        Ok(link)
+    }
+
+    async fn link_by_id(&self, link_id: u64) -> StorageResult<Option<NoteLink>> {
+        let collection = self.links_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get(link_id as usize).cloned().flatten())
+    }
+
+    async fn link_by_token(&self, token: &str) -> StorageResult<Option<NoteLink>> {
+        let collection = self.links_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let link = collection.iter()
+            .flatten()
+            .find(|link| link.token == token)
+            .cloned();
+
+        Ok(link)
+    }
+
+    async fn links_by_note(&self, note_id: u64) -> StorageResult<Vec<NoteLink>> {
+        let collection = self.links_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let links = collection.iter()
+            .flatten()
+            .filter(|link| link.note_id == note_id)
+            .cloned()
+            .collect();
+
+        Ok(links)
+    }
+
+    async fn delete_link(&self, link_id: u64) -> StorageResult<Option<NoteLink>> {
+        let mut collection = self.links_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get_mut(link_id as usize).and_then(|link| link.take()))
+    }
+}
+
+#[async_trait]
+impl SessionsRepository for InMemorySessionsRepository {
+    async fn insert_session(
+        &self, new_session: NewSession,
+    ) -> StorageResult<Session> {
+        let mut collection = self.sessions_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let session = Session {
+            id: collection.len() as u32,
+            user_id: new_session.user_id,
+            created_at: new_session.created_at,
+            last_seen_at: new_session.created_at,
+            expires_at: new_session.expires_at,
+            device_label: new_session.client.device_label,
+            user_agent: new_session.client.user_agent,
+            ip: new_session.client.ip,
+        };
+
+        collection.push(Some(session.clone()));
+
+        Ok(session)
+    }
+
+    async fn session_by_id(
+        &self, session_id: u32,
+    ) -> StorageResult<Option<Session>> {
+        let collection = self.sessions_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get(session_id as usize).cloned().flatten())
+    }
+
+    async fn sessions_by_user(&self, user_id: u32) -> StorageResult<Vec<Session>> {
+        let collection = self.sessions_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let sessions = collection.iter()
+            .flatten()
+            .filter(|session| session.user_id == user_id)
+            .cloned()
+            .collect();
+
+        Ok(sessions)
+    }
+
+    async fn update_session(
+        &self, session: Session,
+    ) -> StorageResult<Option<Session>> {
+        let mut collection = self.sessions_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        match collection.get_mut(session.id as usize) {
+            Some(slot @ Some(_)) => {
+                *slot = Some(session.clone());
+                Ok(Some(session))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn delete_session(
+        &self, session_id: u32,
+    ) -> StorageResult<Option<Session>> {
+        let mut collection = self.sessions_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get_mut(session_id as usize)
+            .and_then(|session| session.take()))
+    }
+}
+
+#[async_trait]
+impl RefreshTokensRepository for InMemoryRefreshTokensRepository {
+    async fn insert_refresh_token(
+        &self, new_refresh_token: NewRefreshToken,
+    ) -> StorageResult<RefreshToken> {
+        let mut collection = self.refresh_tokens_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let is_token_taken = collection.iter()
+            .flatten()
+            .any(|refresh_token| refresh_token.token_hash == new_refresh_token.token_hash);
+
+        if is_token_taken {
+            return Err(StorageError::Conflict);
+        }
+
+        let refresh_token = RefreshToken {
+            id: collection.len() as u64,
+            session_id: new_refresh_token.session_id,
+            token_hash: new_refresh_token.token_hash,
+            created_at: new_refresh_token.created_at,
+            used_at: None,
+        };
+
+        collection.push(Some(refresh_token.clone()));
+
+        Ok(refresh_token)
+    }
+
+    async fn refresh_token_by_hash(
+        &self, token_hash: &str,
+    ) -> StorageResult<Option<RefreshToken>> {
+        let collection = self.refresh_tokens_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let refresh_token = collection.iter()
+            .flatten()
+            .find(|refresh_token| refresh_token.token_hash == token_hash)
+            .cloned();
+
+        Ok(refresh_token)
+    }
+
+    async fn refresh_tokens_by_session(
+        &self, session_id: u32,
+    ) -> StorageResult<Vec<RefreshToken>> {
+        let collection = self.refresh_tokens_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        let refresh_tokens = collection.iter()
+            .flatten()
+            .filter(|refresh_token| refresh_token.session_id == session_id)
+            .cloned()
+            .collect();
+
+        Ok(refresh_tokens)
+    }
+
+    async fn use_refresh_token(
+        &self, refresh_token_id: u64, used_at: DateTime<Utc>,
+    ) -> StorageResult<Option<RefreshToken>> {
+        let mut collection = self.refresh_tokens_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        match collection.get_mut(refresh_token_id as usize) {
+            Some(Some(refresh_token)) if refresh_token.used_at.is_none() => {
+                refresh_token.used_at = Some(used_at);
+                Ok(Some(refresh_token.clone()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn delete_refresh_token(
+        &self, refresh_token_id: u64,
+    ) -> StorageResult<Option<RefreshToken>> {
+        let mut collection = self.refresh_tokens_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        Ok(collection.get_mut(refresh_token_id as usize)
+            .and_then(|refresh_token| refresh_token.take()))
+    }
+}
+
+/// Direct row access used by backends that keep their data in memory
+/// and persist it elsewhere (e.g. the journal backend).
+pub trait Restorable {
+    type Row: Clone;
+
+    fn rows(&self) -> StorageResult<Vec<Option<Self::Row>>>;
+
+    fn row(&self, id: usize) -> StorageResult<Option<Self::Row>>;
+
+    fn restore_row(&self, id: usize, row: Option<Self::Row>) -> StorageResult<()>;
+}
+
+fn restore_row<T>(collection: &mut Vec<Option<T>>, id: usize, row: Option<T>) {
+    if collection.len() <= id {
+        collection.resize_with(id + 1, || None);
+    }
+
+    collection[id] = row;
+}
+
+impl Restorable for InMemoryUsersRepository {
+    type Row = User;
+
+    fn rows(&self) -> StorageResult<Vec<Option<User>>> {
+        self.users_collection.lock()
+            .map(|collection| collection.clone())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn row(&self, id: usize) -> StorageResult<Option<User>> {
+        self.users_collection.lock()
+            .map(|collection| collection.get(id).cloned().flatten())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn restore_row(&self, id: usize, row: Option<User>) -> StorageResult<()> {
+        let mut collection = self.users_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        restore_row(&mut collection, id, row);
+
+        Ok(())
+    }
+}
+
+impl Restorable for InMemoryNotesRepository {
+    type Row = Note;
+
+    fn rows(&self) -> StorageResult<Vec<Option<Note>>> {
+        self.notes_collection.lock()
+            .map(|collection| collection.clone())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn row(&self, id: usize) -> StorageResult<Option<Note>> {
+        self.notes_collection.lock()
+            .map(|collection| collection.get(id).cloned().flatten())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn restore_row(&self, id: usize, row: Option<Note>) -> StorageResult<()> {
+        let mut collection = self.notes_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        restore_row(&mut collection, id, row);
+
+        Ok(())
+    }
+}
+
+impl Restorable for InMemoryNotebooksRepository {
+    type Row = Notebook;
+
+    fn rows(&self) -> StorageResult<Vec<Option<Notebook>>> {
+        self.notebooks_collection.lock()
+            .map(|collection| collection.clone())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn row(&self, id: usize) -> StorageResult<Option<Notebook>> {
+        self.notebooks_collection.lock()
+            .map(|collection| collection.get(id).cloned().flatten())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn restore_row(&self, id: usize, row: Option<Notebook>) -> StorageResult<()> {
+        let mut collection = self.notebooks_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        restore_row(&mut collection, id, row);
+
+        Ok(())
+    }
+}
+
+impl Restorable for InMemoryRevisionsRepository {
+    type Row = NoteRevision;
+
+    fn rows(&self) -> StorageResult<Vec<Option<NoteRevision>>> {
+        self.revisions_collection.lock()
+            .map(|collection| collection.clone())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn row(&self, id: usize) -> StorageResult<Option<NoteRevision>> {
+        self.revisions_collection.lock()
+            .map(|collection| collection.get(id).cloned().flatten())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn restore_row(&self, id: usize, row: Option<NoteRevision>) -> StorageResult<()> {
+        let mut collection = self.revisions_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        restore_row(&mut collection, id, row);
+
+        Ok(())
+    }
+}
+
+impl Restorable for InMemorySharesRepository {
+    type Row = NoteShare;
+
+    fn rows(&self) -> StorageResult<Vec<Option<NoteShare>>> {
+        self.shares_collection.lock()
+            .map(|collection| collection.clone())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn row(&self, id: usize) -> StorageResult<Option<NoteShare>> {
+        self.shares_collection.lock()
+            .map(|collection| collection.get(id).cloned().flatten())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn restore_row(&self, id: usize, row: Option<NoteShare>) -> StorageResult<()> {
+        let mut collection = self.shares_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        restore_row(&mut collection, id, row);
+
+        Ok(())
+    }
+}
+
+impl Restorable for InMemoryShareEventsRepository {
+    type Row = ShareEvent;
+
+    fn rows(&self) -> StorageResult<Vec<Option<ShareEvent>>> {
+        self.share_events_collection.lock()
+            .map(|collection| collection.clone())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn row(&self, id: usize) -> StorageResult<Option<ShareEvent>> {
+        self.share_events_collection.lock()
+            .map(|collection| collection.get(id).cloned().flatten())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn restore_row(&self, id: usize, row: Option<ShareEvent>) -> StorageResult<()> {
+        let mut collection = self.share_events_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        restore_row(&mut collection, id, row);
+
+        Ok(())
+    }
+}
+
+impl Restorable for InMemoryLinksRepository {
+    type Row = NoteLink;
+
+    fn rows(&self) -> StorageResult<Vec<Option<NoteLink>>> {
+        self.links_collection.lock()
+            .map(|collection| collection.clone())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn row(&self, id: usize) -> StorageResult<Option<NoteLink>> {
+        self.links_collection.lock()
+            .map(|collection| collection.get(id).cloned().flatten())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn restore_row(&self, id: usize, row: Option<NoteLink>) -> StorageResult<()> {
+        let mut collection = self.links_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        restore_row(&mut collection, id, row);
+
+        Ok(())
+    }
+}
+
+impl Restorable for InMemorySessionsRepository {
+    type Row = Session;
+
+    fn rows(&self) -> StorageResult<Vec<Option<Session>>> {
+        self.sessions_collection.lock()
+            .map(|collection| collection.clone())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn row(&self, id: usize) -> StorageResult<Option<Session>> {
+        self.sessions_collection.lock()
+            .map(|collection| collection.get(id).cloned().flatten())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn restore_row(&self, id: usize, row: Option<Session>) -> StorageResult<()> {
+        let mut collection = self.sessions_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        restore_row(&mut collection, id, row);
+
+        Ok(())
+    }
+}
+
+impl Restorable for InMemoryRefreshTokensRepository {
+    type Row = RefreshToken;
+
+    fn rows(&self) -> StorageResult<Vec<Option<RefreshToken>>> {
+        self.refresh_tokens_collection.lock()
+            .map(|collection| collection.clone())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn row(&self, id: usize) -> StorageResult<Option<RefreshToken>> {
+        self.refresh_tokens_collection.lock()
+            .map(|collection| collection.get(id).cloned().flatten())
+            .map_err(|_| StorageError::Unavailable)
+    }
+
+    fn restore_row(&self, id: usize, row: Option<RefreshToken>) -> StorageResult<()> {
+        let mut collection = self.refresh_tokens_collection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        restore_row(&mut collection, id, row);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use crate::model::links::links_repository::LinksRepository;
+use crate::model::notebooks::notebooks_repository::NotebooksRepository;
+use crate::model::notes::notes_repository::NotesRepository;
+use crate::model::revisions::revisions_repository::RevisionsRepository;
+use crate::model::sessions::sessions_repository::{RefreshTokensRepository, SessionsRepository};
+use crate::model::shares::shares_repository::{ShareEventsRepository, SharesRepository};
+use crate::model::storage::journal::JournalStorage;
+use crate::model::storage::memory::{
+    InMemoryLinksRepository,
+    InMemoryNotebooksRepository,
+    InMemoryNotesRepository,
+    InMemoryRefreshTokensRepository,
+    InMemoryRevisionsRepository,
+    InMemorySessionsRepository,
+    InMemoryShareEventsRepository,
+    InMemorySharesRepository,
+    InMemoryUsersRepository,
+};
+use crate::model::storage::sqlite::SqliteStorage;
+use crate::model::users::users_repository::UsersRepository;
+use crate::settings::{Storage, StorageBackend};
+
+pub mod journal;
+pub mod memory;
+pub mod sqlite;
+
+pub struct Repositories {
+    pub users: Arc<dyn UsersRepository>,
+    pub notes: Arc<dyn NotesRepository>,
+    pub notebooks: Arc<dyn NotebooksRepository>,
+    pub revisions: Arc<dyn RevisionsRepository>,
+    pub sessions: Arc<dyn SessionsRepository>,
+    pub refresh_tokens: Arc<dyn RefreshTokensRepository>,
+    pub shares: Arc<dyn SharesRepository>,
+    pub share_events: Arc<dyn ShareEventsRepository>,
+    pub links: Arc<dyn LinksRepository>,
+}
+
+impl Repositories {
+    pub fn new(storage: &Storage) -> Self {
+        match storage.backend {
+            StorageBackend::Memory => Self {
+                users: Arc::new(InMemoryUsersRepository::default()),
+                notes: Arc::new(InMemoryNotesRepository::default()),
+                notebooks: Arc::new(InMemoryNotebooksRepository::default()),
+                revisions: Arc::new(InMemoryRevisionsRepository::default()),
+                sessions: Arc::new(InMemorySessionsRepository::default()),
+                refresh_tokens: Arc::new(InMemoryRefreshTokensRepository::default()),
+                shares: Arc::new(InMemorySharesRepository::default()),
+                share_events: Arc::new(InMemoryShareEventsRepository::default()),
+                links: Arc::new(InMemoryLinksRepository::default()),
+            },
+            StorageBackend::Sqlite { ref path } => {
+                let storage = SqliteStorage::open(path)
+                    .expect("SQLite storage must be opened");
+
+                Self {
+                    users: Arc::new(storage.clone()),
+                    notes: Arc::new(storage.clone()),
+                    notebooks: Arc::new(storage.clone()),
+                    revisions: Arc::new(storage.clone()),
+                    sessions: Arc::new(storage.clone()),
+                    refresh_tokens: Arc::new(storage.clone()),
+                    shares: Arc::new(storage.clone()),
+                    share_events: Arc::new(storage.clone()),
+                    links: Arc::new(storage),
+                }
+            }
+            StorageBackend::Journal {
+                ref directory, ref fsync, snapshot_every,
+            } => {
+                let storage = Arc::new(
+                    JournalStorage::open(directory, fsync.clone(), snapshot_every)
+                        .expect("Journal storage must be opened")
+                );
+
+                Self {
+                    users: storage.clone(),
+                    notes: storage.clone(),
+                    notebooks: storage.clone(),
+                    revisions: storage.clone(),
+                    sessions: storage.clone(),
+                    refresh_tokens: storage.clone(),
+                    shares: storage.clone(),
+                    share_events: storage.clone(),
+                    links: storage,
+                }
+            }
+        }
+    }
+}
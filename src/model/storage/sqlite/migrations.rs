@@ -0,0 +1,332 @@
+use chrono::Utc;
+use rusqlite::{params, Connection};
+
+use crate::log::log_layer;
+
+const MIGRATIONS_LAYER: &str = "MIGRATIONS";
+
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// Ordered list of schema migrations. Applied migrations are recorded in
+/// `schema_migrations`, so new ones must only ever be appended.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_users_notes_sessions",
+        sql: "
+            CREATE TABLE users (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                nickname TEXT NOT NULL UNIQUE,
+                password TEXT NOT NULL
+            );
+
+            CREATE TABLE notes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                creator_id INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                body TEXT NOT NULL
+            );
+
+            CREATE INDEX notes_creator_id ON notes (creator_id);
+
+            CREATE TABLE sessions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX sessions_user_id ON sessions (user_id);
+        ",
+    },
+    Migration {
+        version: 2,
+        name: "add_users_created_at",
+        sql: "
+            ALTER TABLE users ADD COLUMN created_at TEXT NOT NULL DEFAULT '';
+
+            UPDATE users
+            SET created_at = strftime('%Y-%m-%d %H:%M:%f+00:00', 'now');
+        ",
+    },
+    Migration {
+        version: 3,
+        name: "add_users_role",
+        sql: "ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'user';",
+    },
+    Migration {
+        version: 4,
+        name: "add_notes_timestamps_and_last_editor",
+        sql: "
+            ALTER TABLE notes ADD COLUMN created_at TEXT NOT NULL DEFAULT '';
+            ALTER TABLE notes ADD COLUMN updated_at TEXT NOT NULL DEFAULT '';
+            ALTER TABLE notes ADD COLUMN last_editor_id INTEGER NOT NULL DEFAULT 0;
+
+            UPDATE notes
+            SET created_at = strftime('%Y-%m-%d %H:%M:%f+00:00', 'now'),
+                updated_at = strftime('%Y-%m-%d %H:%M:%f+00:00', 'now'),
+                last_editor_id = creator_id;
+        ",
+    },
+    Migration {
+        version: 5,
+        name: "create_tags",
+        sql: "
+            CREATE TABLE tags (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                owner_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                UNIQUE (owner_id, name)
+            );
+
+            CREATE TABLE note_tags (
+                note_id INTEGER NOT NULL REFERENCES notes (id) ON DELETE CASCADE,
+                tag_id INTEGER NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+                PRIMARY KEY (note_id, tag_id)
+            );
+
+            CREATE INDEX note_tags_tag_id ON note_tags (tag_id);
+        ",
+    },
+    Migration {
+        version: 6,
+        name: "create_notebooks",
+        sql: "
+            CREATE TABLE notebooks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                owner_id INTEGER NOT NULL,
+                parent_id INTEGER REFERENCES notebooks (id),
+                name TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX notebooks_owner_id ON notebooks (owner_id);
+
+            ALTER TABLE notes ADD COLUMN notebook_id INTEGER REFERENCES notebooks (id);
+        ",
+    },
+    Migration {
+        version: 7,
+        name: "create_note_revisions",
+        sql: "
+            CREATE TABLE note_revisions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                note_id INTEGER NOT NULL REFERENCES notes (id) ON DELETE CASCADE,
+                editor_id INTEGER NOT NULL,
+                edited_at TEXT NOT NULL,
+                title TEXT NOT NULL,
+                body TEXT NOT NULL
+            );
+
+            CREATE INDEX note_revisions_note_id ON note_revisions (note_id);
+        ",
+    },
+    Migration {
+        version: 8,
+        name: "add_notes_version",
+        sql: "ALTER TABLE notes ADD COLUMN version INTEGER NOT NULL DEFAULT 1;",
+    },
+    Migration {
+        version: 9,
+        name: "add_notes_deleted_at",
+        sql: "ALTER TABLE notes ADD COLUMN deleted_at TEXT;",
+    },
+    Migration {
+        version: 10,
+        name: "create_note_shares_and_share_events",
+        sql: "
+            CREATE TABLE note_shares (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                note_id INTEGER NOT NULL REFERENCES notes (id) ON DELETE CASCADE,
+                owner_id INTEGER NOT NULL,
+                user_id INTEGER NOT NULL,
+                permission TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                UNIQUE (note_id, user_id)
+            );
+
+            CREATE INDEX note_shares_user_id ON note_shares (user_id);
+
+            CREATE TABLE share_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                note_id INTEGER NOT NULL,
+                actor_id INTEGER NOT NULL,
+                user_id INTEGER NOT NULL,
+                action TEXT NOT NULL,
+                permission TEXT NOT NULL,
+                occurred_at TEXT NOT NULL
+            );
+
+            CREATE INDEX share_events_note_id ON share_events (note_id);
+        ",
+    },
+    Migration {
+        version: 11,
+        name: "create_note_links",
+        sql: "
+            CREATE TABLE note_links (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                note_id INTEGER NOT NULL REFERENCES notes (id) ON DELETE CASCADE,
+                owner_id INTEGER NOT NULL,
+                token TEXT NOT NULL UNIQUE,
+                password_hash TEXT,
+                expires_at TEXT,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX note_links_note_id ON note_links (note_id);
+        ",
+    },
+    Migration {
+        version: 12,
+        name: "add_sessions_created_at_and_last_seen_at",
+        sql: "
+            ALTER TABLE sessions ADD COLUMN created_at TEXT NOT NULL DEFAULT '';
+            ALTER TABLE sessions ADD COLUMN last_seen_at TEXT NOT NULL DEFAULT '';
+
+            UPDATE sessions
+            SET created_at = strftime('%Y-%m-%d %H:%M:%f+00:00', 'now'),
+                last_seen_at = strftime('%Y-%m-%d %H:%M:%f+00:00', 'now');
+        ",
+    },
+    Migration {
+        version: 13,
+        name: "add_sessions_client",
+        sql: "
+            ALTER TABLE sessions ADD COLUMN device_label TEXT;
+            ALTER TABLE sessions ADD COLUMN user_agent TEXT;
+            ALTER TABLE sessions ADD COLUMN ip TEXT;
+        ",
+    },
+    Migration {
+        version: 14,
+        name: "create_refresh_tokens",
+        sql: "
+            CREATE TABLE refresh_tokens (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id INTEGER NOT NULL REFERENCES sessions (id) ON DELETE CASCADE,
+                token TEXT NOT NULL UNIQUE,
+                created_at TEXT NOT NULL,
+                used_at TEXT
+            );
+
+            CREATE INDEX refresh_tokens_session_id ON refresh_tokens (session_id);
+        ",
+    },
+    Migration {
+        version: 15,
+        name: "hash_refresh_tokens",
+        sql: "
+            DELETE FROM refresh_tokens;
+
+            ALTER TABLE refresh_tokens RENAME COLUMN token TO token_hash;
+        ",
+    },
+    Migration {
+        version: 16,
+        name: "index_notes_sort_keys",
+        sql: "
+            CREATE INDEX notes_creator_id_created_at ON notes (creator_id, created_at, id);
+            CREATE INDEX notes_creator_id_updated_at ON notes (creator_id, updated_at, id);
+        ",
+    },
+];
+
+pub fn apply_migrations(connection: &mut Connection) -> rusqlite::Result<()> {
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at INTEGER NOT NULL
+        );",
+    )?;
+
+    let current_version: u32 = connection.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )?;
+
+    for migration in MIGRATIONS.iter()
+        .filter(|migration| migration.version > current_version)
+    {
+        let transaction = connection.transaction()?;
+
+        transaction.execute_batch(migration.sql)?;
+        transaction.execute(
+            "INSERT INTO schema_migrations (version, name, applied_at) \
+            VALUES (?1, ?2, ?3)",
+            params![migration.version, migration.name, Utc::now().timestamp()],
+        )?;
+
+        transaction.commit()?;
+
+        log_layer(
+            MIGRATIONS_LAYER,
+            format!("applied {} {}", migration.version, migration.name).as_str(),
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied_versions(connection: &Connection) -> Vec<u32> {
+        connection.prepare("SELECT version FROM schema_migrations ORDER BY version")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    fn schema(connection: &Connection) -> Vec<String> {
+        connection.prepare("SELECT sql FROM sqlite_schema WHERE sql IS NOT NULL ORDER BY name")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn versions_are_consecutive() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as u32 + 1, "{}", migration.name);
+        }
+    }
+
+    #[test]
+    fn applies_every_migration() {
+        let mut connection = Connection::open_in_memory().unwrap();
+
+        apply_migrations(&mut connection).unwrap();
+
+        assert_eq!(
+            applied_versions(&connection),
+            MIGRATIONS.iter().map(|migration| migration.version).collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn reapplying_is_a_no_op() {
+        let mut connection = Connection::open_in_memory().unwrap();
+
+        apply_migrations(&mut connection).unwrap();
+        let versions = applied_versions(&connection);
+        let tables = schema(&connection);
+
+        apply_migrations(&mut connection).unwrap();
+
+        assert_eq!(applied_versions(&connection), versions);
+        assert_eq!(schema(&connection), tables);
+    }
+}
@@ -0,0 +1,1317 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, params_from_iter, Connection, ErrorCode, OptionalExtension, Row, ToSql};
+use rusqlite::functions::FunctionFlags;
+
+use crate::error::{StorageError, StorageResult};
+use crate::model::links::links_models::{NewNoteLink, NoteLink};
+use crate::model::links::links_repository::LinksRepository;
+use crate::model::notebooks::notebooks_models::{NewNotebook, Notebook};
+use crate::model::notebooks::notebooks_repository::NotebooksRepository;
+use crate::model::notes::notes_models::{NewNote, Note};
+use crate::model::notes::notes_query::{
+    NotesFilter,
+    NotesSlice,
+    NotesSort,
+    SortKey,
+    SortOrder,
+    TagMode,
+};
+use crate::model::notes::notes_repository::NotesRepository;
+use crate::model::notes::notes_tags::TAG_SEPARATOR;
+use crate::model::revisions::revisions_models::{NewNoteRevision, NoteRevision};
+use crate::model::revisions::revisions_repository::RevisionsRepository;
+use crate::model::sessions::sessions_models::{
+    NewRefreshToken,
+    NewSession,
+    RefreshToken,
+    Session,
+};
+use crate::model::sessions::sessions_repository::{RefreshTokensRepository, SessionsRepository};
+use crate::model::shares::shares_models::{
+    NewNoteShare,
+    NewShareEvent,
+    NoteShare,
+    ShareAction,
+    ShareEvent,
+    SharePermission,
+};
+use crate::model::shares::shares_repository::{ShareEventsRepository, SharesRepository};
+use crate::model::storage::sqlite::migrations::apply_migrations;
+use crate::model::users::users_models::{NewUser, User, UserRole};
+use crate::model::users::users_repository::UsersRepository;
+
+pub mod migrations;
+
+const USER_COLUMNS: &str = "id, name, nickname, password, created_at, role";
+const NOTE_COLUMNS: &str =
+    "id, creator_id, title, body, created_at, updated_at, last_editor_id, notebook_id, \
+    version, deleted_at, \
+    (SELECT group_concat(tags.name, ',') FROM note_tags \
+    JOIN tags ON tags.id = note_tags.tag_id WHERE note_tags.note_id = notes.id)";
+const NOTEBOOK_COLUMNS: &str = "id, owner_id, parent_id, name, position, created_at";
+const REVISION_COLUMNS: &str = "id, note_id, editor_id, edited_at, title, body";
+const SESSION_COLUMNS: &str =
+    "id, user_id, expires_at, created_at, last_seen_at, device_label, user_agent, ip";
+const REFRESH_TOKEN_COLUMNS: &str = "id, session_id, token_hash, created_at, used_at";
+const LINK_COLUMNS: &str =
+    "id, note_id, owner_id, token, password_hash, expires_at, created_at";
+const SHARE_COLUMNS: &str = "id, note_id, owner_id, user_id, permission, created_at";
+const SHARE_EVENT_COLUMNS: &str =
+    "id, note_id, actor_id, user_id, action, permission, occurred_at";
+
+/// Single SQLite connection shared by all repositories.
+#[derive(Clone)]
+pub struct SqliteStorage {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        let mut connection = Connection::open(path)?;
+
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.pragma_update(None, "foreign_keys", true)?;
+
+        //SQLite's own lower() only knows ASCII, titles are sorted and
+        //matched lowercase the way the other backends do it
+        connection.create_scalar_function(
+            "unicode_lower",
+            1,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |context| Ok(context.get::<String>(0)?.to_lowercase()),
+        )?;
+
+        apply_migrations(&mut connection)?;
+
+        Ok(Self { connection: Arc::new(Mutex::new(connection)) })
+    }
+
+    fn with_connection<T>(
+        &self,
+        action: impl FnOnce(&Connection) -> rusqlite::Result<T>,
+    ) -> StorageResult<T> {
+        let connection = self.connection.lock()
+            .map_err(|_| StorageError::Unavailable)?;
+
+        action(&connection).map_err(storage_error)
+    }
+}
+
+fn storage_error(error: rusqlite::Error) -> StorageError {
+    match error.sqlite_error_code() {
+        Some(ErrorCode::ConstraintViolation) => StorageError::Conflict,
+        _ => StorageError::Unavailable,
+    }
+}
+
+fn user_from_row(row: &Row) -> rusqlite::Result<User> {
+    Ok(User {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        nickname: row.get(2)?,
+        password: row.get(3)?,
+        created_at: row.get(4)?,
+        role: UserRole::parse(row.get_ref(5)?.as_str()?)
+            .ok_or(rusqlite::Error::InvalidColumnType(
+                5, "role".to_string(), rusqlite::types::Type::Text,
+            ))?,
+    })
+}
+
+fn note_from_row(row: &Row) -> rusqlite::Result<Note> {
+    Ok(Note {
+        id: row.get(0)?,
+        creator_id: row.get(1)?,
+        title: row.get(2)?,
+        body: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+        last_editor_id: row.get(6)?,
+        notebook_id: row.get(7)?,
+        version: row.get(8)?,
+        deleted_at: row.get(9)?,
+        tags: tags_from_column(row.get(10)?),
+    })
+}
+
+fn tags_from_column(tags: Option<String>) -> Vec<String> {
+    let mut tags = tags.map(|tags| tags.split(TAG_SEPARATOR)
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+        )
+        .unwrap_or_default();
+
+    tags.sort();
+    tags
+}
+
+fn select_note(connection: &Connection, note_id: u64) -> rusqlite::Result<Option<Note>> {
+    connection.query_row(
+        &format!("SELECT {NOTE_COLUMNS} FROM notes WHERE id = ?1"),
+        params![note_id],
+        note_from_row,
+    ).optional()
+}
+
+/// `WHERE` conditions of the filter apart from its cursor, with their values.
+fn filter_conditions(filter: &NotesFilter) -> (Vec<String>, Vec<Box<dyn ToSql>>) {
+    let mut conditions = vec![
+        "creator_id = ?".to_string(),
+        "deleted_at IS NULL".to_string(),
+    ];
+    let mut values: Vec<Box<dyn ToSql>> = vec![Box::new(filter.creator_id)];
+
+    if let Some(notebook_ids) = &filter.notebook_ids {
+        conditions.push(format!("notebook_id IN ({})", placeholders(notebook_ids.len())));
+        values.extend(notebook_ids.iter().map(|id| Box::new(*id) as Box<dyn ToSql>));
+    }
+
+    if let Some(title) = &filter.title {
+        conditions.push("instr(unicode_lower(title), ?) > 0".to_string());
+        values.push(Box::new(title.clone()));
+    }
+
+    for (condition, time) in [
+        ("created_at >= ?", filter.created_from),
+        ("created_at < ?", filter.created_to),
+        ("updated_at >= ?", filter.updated_from),
+        ("updated_at < ?", filter.updated_to),
+    ] {
+        if let Some(time) = time {
+            conditions.push(condition.to_string());
+            values.push(Box::new(time));
+        }
+    }
+
+    if let Some(tags) = &filter.tags {
+        let mut tags = tags.clone();
+        tags.sort();
+        tags.dedup();
+
+        let matching_tags = format!(
+            "(SELECT count(*) FROM note_tags JOIN tags ON tags.id = note_tags.tag_id \
+            WHERE note_tags.note_id = notes.id AND tags.name IN ({}))",
+            placeholders(tags.len()),
+        );
+
+        conditions.push(match filter.tag_mode {
+            TagMode::And => format!("{matching_tags} = {}", tags.len()),
+            TagMode::Or => format!("{matching_tags} > 0"),
+        });
+        values.extend(tags.into_iter().map(|tag| Box::new(tag) as Box<dyn ToSql>));
+    }
+
+    (conditions, values)
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+fn sort_key_value(key: &SortKey) -> Box<dyn ToSql> {
+    match key.clone() {
+        SortKey::Created(time) | SortKey::Updated(time) => Box::new(time),
+        SortKey::Title(title) => Box::new(title),
+    }
+}
+
+/// Replaces the tags of the note, creating missing tags of the owner and
+/// dropping the ones no note refers to anymore.
+fn set_note_tags(
+    connection: &Connection, note_id: u64, owner_id: u32, tags: &[String],
+) -> rusqlite::Result<()> {
+    connection.execute("DELETE FROM note_tags WHERE note_id = ?1", params![note_id])?;
+
+    for tag in tags {
+        connection.execute(
+            "INSERT INTO tags (owner_id, name) VALUES (?1, ?2) \
+            ON CONFLICT (owner_id, name) DO NOTHING",
+            params![owner_id, tag],
+        )?;
+        connection.execute(
+            "INSERT INTO note_tags (note_id, tag_id) \
+            SELECT ?1, id FROM tags WHERE owner_id = ?2 AND name = ?3",
+            params![note_id, owner_id, tag],
+        )?;
+    }
+
+    delete_unused_tags(connection, owner_id)
+}
+
+fn delete_unused_tags(connection: &Connection, owner_id: u32) -> rusqlite::Result<()> {
+    connection.execute(
+        "DELETE FROM tags WHERE owner_id = ?1 \
+        AND id NOT IN (SELECT tag_id FROM note_tags)",
+        params![owner_id],
+    )?;
+
+    Ok(())
+}
+
+fn notebook_from_row(row: &Row) -> rusqlite::Result<Notebook> {
+    Ok(Notebook {
+        id: row.get(0)?,
+        owner_id: row.get(1)?,
+        parent_id: row.get(2)?,
+        name: row.get(3)?,
+        position: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+fn revision_from_row(row: &Row) -> rusqlite::Result<NoteRevision> {
+    Ok(NoteRevision {
+        id: row.get(0)?,
+        note_id: row.get(1)?,
+        editor_id: row.get(2)?,
+        edited_at: row.get(3)?,
+        title: row.get(4)?,
+        body: row.get(5)?,
+    })
+}
+
+fn share_from_row(row: &Row) -> rusqlite::Result<NoteShare> {
+    Ok(NoteShare {
+        id: row.get(0)?,
+        note_id: row.get(1)?,
+        owner_id: row.get(2)?,
+        user_id: row.get(3)?,
+        permission: permission_from_column(row, 4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+fn share_event_from_row(row: &Row) -> rusqlite::Result<ShareEvent> {
+    Ok(ShareEvent {
+        id: row.get(0)?,
+        note_id: row.get(1)?,
+        actor_id: row.get(2)?,
+        user_id: row.get(3)?,
+        action: ShareAction::parse(row.get_ref(4)?.as_str()?)
+            .ok_or(rusqlite::Error::InvalidColumnType(
+                4, "action".to_string(), rusqlite::types::Type::Text,
+            ))?,
+        permission: permission_from_column(row, 5)?,
+        occurred_at: row.get(6)?,
+    })
+}
+
+fn link_from_row(row: &Row) -> rusqlite::Result<NoteLink> {
+    Ok(NoteLink {
+        id: row.get(0)?,
+        note_id: row.get(1)?,
+        owner_id: row.get(2)?,
+        token: row.get(3)?,
+        password_hash: row.get(4)?,
+        expires_at: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+fn permission_from_column(row: &Row, index: usize) -> rusqlite::Result<SharePermission> {
+    SharePermission::parse(row.get_ref(index)?.as_str()?)
+        .ok_or(rusqlite::Error::InvalidColumnType(
+            index, "permission".to_string(), rusqlite::types::Type::Text,
+        ))
+}
+
+fn session_from_row(row: &Row) -> rusqlite::Result<Session> {
+    Ok(Session {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        expires_at: row.get::<_, i64>(2)? as usize,
+        created_at: row.get(3)?,
+        last_seen_at: row.get(4)?,
+        device_label: row.get(5)?,
+        user_agent: row.get(6)?,
+        ip: row.get(7)?,
+    })
+}
+
+fn refresh_token_from_row(row: &Row) -> rusqlite::Result<RefreshToken> {
+    Ok(RefreshToken {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        token_hash: row.get(2)?,
+        created_at: row.get(3)?,
+        used_at: row.get(4)?,
+    })
+}
+
+#[async_trait]
+impl UsersRepository for SqliteStorage {
+    async fn insert_user(&self, new_user: NewUser) -> StorageResult<User> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "INSERT INTO users (name, nickname, password, created_at, role) \
+                    VALUES (?1, ?2, ?3, ?4, ?5) RETURNING {USER_COLUMNS}"
+                ),
+                params![
+                    new_user.name,
+                    new_user.nickname,
+                    new_user.password,
+                    new_user.created_at,
+                    new_user.role.as_ref(),
+                ],
+                user_from_row,
+            )
+        })
+    }
+
+    async fn user_by_id(&self, user_id: u32) -> StorageResult<Option<User>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!("SELECT {USER_COLUMNS} FROM users WHERE id = ?1"),
+                params![user_id],
+                user_from_row,
+            ).optional()
+        })
+    }
+
+    async fn user_by_nickname(
+        &self, nickname: &str,
+    ) -> StorageResult<Option<User>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!("SELECT {USER_COLUMNS} FROM users WHERE nickname = ?1"),
+                params![nickname],
+                user_from_row,
+            ).optional()
+        })
+    }
+
+    async fn update_user(&self, user: User) -> StorageResult<Option<User>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "UPDATE users \
+                    SET name = ?2, nickname = ?3, password = ?4, role = ?5 \
+                    WHERE id = ?1 RETURNING {USER_COLUMNS}"
+                ),
+                params![
+                    user.id,
+                    user.name,
+                    user.nickname,
+                    user.password,
+                    user.role.as_ref(),
+                ],
+                user_from_row,
+            ).optional()
+        })
+    }
+
+    async fn delete_user(&self, user_id: u32) -> StorageResult<Option<User>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!("DELETE FROM users WHERE id = ?1 RETURNING {USER_COLUMNS}"),
+                params![user_id],
+                user_from_row,
+            ).optional()
+        })
+    }
+}
+
+#[async_trait]
+impl NotesRepository for SqliteStorage {
+    async fn insert_note(&self, new_note: NewNote) -> StorageResult<Note> {
+        self.with_connection(|connection| {
+            let transaction = connection.unchecked_transaction()?;
+
+            let note_id = transaction.query_row(
+                "INSERT INTO notes \
+                (creator_id, title, body, created_at, updated_at, last_editor_id, \
+                notebook_id) \
+                VALUES (?1, ?2, ?3, ?4, ?4, ?1, ?5) RETURNING id",
+                params![
+                    new_note.creator_id,
+                    new_note.title,
+                    new_note.body,
+                    new_note.created_at,
+                    new_note.notebook_id,
+                ],
+                |row| row.get(0),
+            )?;
+
+            set_note_tags(&transaction, note_id, new_note.creator_id, &new_note.tags)?;
+            let note = select_note(&transaction, note_id)?
+                .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+
+            transaction.commit()?;
+
+            Ok(note)
+        })
+    }
+
+    async fn note_by_id(&self, note_id: u64) -> StorageResult<Option<Note>> {
+        self.with_connection(|connection| select_note(connection, note_id))
+    }
+
+    async fn notes_by_creator(&self, creator_id: u32) -> StorageResult<Vec<Note>> {
+        self.with_connection(|connection| {
+            connection.prepare(
+                &format!(
+                    "SELECT {NOTE_COLUMNS} FROM notes \
+                    WHERE creator_id = ?1 ORDER BY id"
+                ),
+            )?
+                .query_map(params![creator_id], note_from_row)?
+                .collect()
+        })
+    }
+
+    async fn notes_by_filter(&self, filter: &NotesFilter) -> StorageResult<NotesSlice> {
+        self.with_connection(|connection| {
+            let (mut conditions, mut values) = filter_conditions(filter);
+
+            let total = connection.query_row(
+                &format!("SELECT count(*) FROM notes WHERE {}", conditions.join(" AND ")),
+                params_from_iter(values.iter()),
+                |row| row.get(0),
+            )?;
+
+            let sort_column = match filter.sort {
+                NotesSort::Created => "created_at",
+                NotesSort::Updated => "updated_at",
+                NotesSort::Title => "unicode_lower(title)",
+            };
+            let (direction, comparison) = match filter.order {
+                SortOrder::Asc => ("ASC", ">"),
+                SortOrder::Desc => ("DESC", "<"),
+            };
+
+            if let Some((key, id)) = &filter.after {
+                conditions.push(format!(
+                    "({sort_column} {comparison} ? \
+                    OR ({sort_column} = ? AND id {comparison} ?))"
+                ));
+                values.push(sort_key_value(key));
+                values.push(sort_key_value(key));
+                values.push(Box::new(*id));
+            }
+
+            values.push(Box::new(filter.limit));
+
+            let notes = connection.prepare(
+                &format!(
+                    "SELECT {NOTE_COLUMNS} FROM notes WHERE {} \
+                    ORDER BY {sort_column} {direction}, id {direction} LIMIT ?",
+                    conditions.join(" AND "),
+                ),
+            )?
+                .query_map(params_from_iter(values.iter()), note_from_row)?
+                .collect::<rusqlite::Result<_>>()?;
+
+            Ok(NotesSlice { notes, total })
+        })
+    }
+
+    async fn all_notes(&self) -> StorageResult<Vec<Note>> {
+        self.with_connection(|connection| {
+            connection.prepare(
+                &format!("SELECT {NOTE_COLUMNS} FROM notes ORDER BY id"),
+            )?
+                .query_map([], note_from_row)?
+                .collect()
+        })
+    }
+
+    async fn update_note(&self, note: Note) -> StorageResult<Option<Note>> {
+        self.with_connection(|connection| {
+            let transaction = connection.unchecked_transaction()?;
+
+            let updated = transaction.execute(
+                "UPDATE notes \
+                SET creator_id = ?2, title = ?3, body = ?4, \
+                created_at = ?5, updated_at = ?6, last_editor_id = ?7, \
+                notebook_id = ?8, version = ?9, deleted_at = ?10 \
+                WHERE id = ?1 AND version = ?9 - 1",
+                params![
+                    note.id,
+                    note.creator_id,
+                    note.title,
+                    note.body,
+                    note.created_at,
+                    note.updated_at,
+                    note.last_editor_id,
+                    note.notebook_id,
+                    note.version,
+                    note.deleted_at,
+                ],
+            )?;
+
+            if updated == 0 {
+                return match select_note(&transaction, note.id)? {
+                    Some(_) => Ok(Err(StorageError::Conflict)),
+                    None => Ok(Ok(None)),
+                };
+            }
+
+            set_note_tags(&transaction, note.id, note.creator_id, &note.tags)?;
+            let note = select_note(&transaction, note.id)?;
+
+            transaction.commit()?;
+
+            Ok(Ok(note))
+        })?
+    }
+
+    async fn delete_note(&self, note_id: u64) -> StorageResult<Option<Note>> {
+        self.with_connection(|connection| {
+            let transaction = connection.unchecked_transaction()?;
+
+            let Some(note) = select_note(&transaction, note_id)? else {
+                return Ok(None);
+            };
+
+            transaction.execute("DELETE FROM notes WHERE id = ?1", params![note_id])?;
+            delete_unused_tags(&transaction, note.creator_id)?;
+
+            transaction.commit()?;
+
+            Ok(Some(note))
+        })
+    }
+}
+
+#[async_trait]
+impl NotebooksRepository for SqliteStorage {
+    async fn insert_notebook(
+        &self, new_notebook: NewNotebook,
+    ) -> StorageResult<Notebook> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "INSERT INTO notebooks \
+                    (owner_id, parent_id, name, position, created_at) \
+                    VALUES (?1, ?2, ?3, ?4, ?5) RETURNING {NOTEBOOK_COLUMNS}"
+                ),
+                params![
+                    new_notebook.owner_id,
+                    new_notebook.parent_id,
+                    new_notebook.name,
+                    new_notebook.position,
+                    new_notebook.created_at,
+                ],
+                notebook_from_row,
+            )
+        })
+    }
+
+    async fn notebook_by_id(
+        &self, notebook_id: u64,
+    ) -> StorageResult<Option<Notebook>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!("SELECT {NOTEBOOK_COLUMNS} FROM notebooks WHERE id = ?1"),
+                params![notebook_id],
+                notebook_from_row,
+            ).optional()
+        })
+    }
+
+    async fn notebooks_by_owner(&self, owner_id: u32) -> StorageResult<Vec<Notebook>> {
+        self.with_connection(|connection| {
+            connection.prepare(
+                &format!(
+                    "SELECT {NOTEBOOK_COLUMNS} FROM notebooks \
+                    WHERE owner_id = ?1 ORDER BY id"
+                ),
+            )?
+                .query_map(params![owner_id], notebook_from_row)?
+                .collect()
+        })
+    }
+
+    async fn update_notebook(
+        &self, notebook: Notebook,
+    ) -> StorageResult<Option<Notebook>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "UPDATE notebooks \
+                    SET owner_id = ?2, parent_id = ?3, name = ?4, position = ?5, \
+                    created_at = ?6 \
+                    WHERE id = ?1 RETURNING {NOTEBOOK_COLUMNS}"
+                ),
+                params![
+                    notebook.id,
+                    notebook.owner_id,
+                    notebook.parent_id,
+                    notebook.name,
+                    notebook.position,
+                    notebook.created_at,
+                ],
+                notebook_from_row,
+            ).optional()
+        })
+    }
+
+    async fn delete_notebook(
+        &self, notebook_id: u64,
+    ) -> StorageResult<Option<Notebook>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "DELETE FROM notebooks WHERE id = ?1 RETURNING {NOTEBOOK_COLUMNS}"
+                ),
+                params![notebook_id],
+                notebook_from_row,
+            ).optional()
+        })
+    }
+}
+
+#[async_trait]
+impl RevisionsRepository for SqliteStorage {
+    async fn insert_revision(
+        &self, new_revision: NewNoteRevision,
+    ) -> StorageResult<NoteRevision> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "INSERT INTO note_revisions \
+                    (note_id, editor_id, edited_at, title, body) \
+                    VALUES (?1, ?2, ?3, ?4, ?5) RETURNING {REVISION_COLUMNS}"
+                ),
+                params![
+                    new_revision.note_id,
+                    new_revision.editor_id,
+                    new_revision.edited_at,
+                    new_revision.title,
+                    new_revision.body,
+                ],
+                revision_from_row,
+            )
+        })
+    }
+
+    async fn revision_by_id(
+        &self, revision_id: u64,
+    ) -> StorageResult<Option<NoteRevision>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!("SELECT {REVISION_COLUMNS} FROM note_revisions WHERE id = ?1"),
+                params![revision_id],
+                revision_from_row,
+            ).optional()
+        })
+    }
+
+    async fn revisions_by_note(&self, note_id: u64) -> StorageResult<Vec<NoteRevision>> {
+        self.with_connection(|connection| {
+            connection.prepare(
+                &format!(
+                    "SELECT {REVISION_COLUMNS} FROM note_revisions \
+                    WHERE note_id = ?1 ORDER BY id"
+                ),
+            )?
+                .query_map(params![note_id], revision_from_row)?
+                .collect()
+        })
+    }
+
+    async fn delete_revision(
+        &self, revision_id: u64,
+    ) -> StorageResult<Option<NoteRevision>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "DELETE FROM note_revisions WHERE id = ?1 RETURNING {REVISION_COLUMNS}"
+                ),
+                params![revision_id],
+                revision_from_row,
+            ).optional()
+        })
+    }
+}
+
+#[async_trait]
+impl SessionsRepository for SqliteStorage {
+    async fn insert_session(
+        &self, new_session: NewSession,
+    ) -> StorageResult<Session> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "INSERT INTO sessions \
+                    (user_id, expires_at, created_at, last_seen_at, device_label, user_agent, ip) \
+                    VALUES (?1, ?2, ?3, ?3, ?4, ?5, ?6) RETURNING {SESSION_COLUMNS}"
+                ),
+                params![
+                    new_session.user_id,
+                    new_session.expires_at as i64,
+                    new_session.created_at,
+                    new_session.client.device_label,
+                    new_session.client.user_agent,
+                    new_session.client.ip,
+                ],
+                session_from_row,
+            )
+        })
+    }
+
+    async fn session_by_id(
+        &self, session_id: u32,
+    ) -> StorageResult<Option<Session>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!("SELECT {SESSION_COLUMNS} FROM sessions WHERE id = ?1"),
+                params![session_id],
+                session_from_row,
+            ).optional()
+        })
+    }
+
+    async fn sessions_by_user(&self, user_id: u32) -> StorageResult<Vec<Session>> {
+        self.with_connection(|connection| {
+            connection.prepare(
+                &format!(
+                    "SELECT {SESSION_COLUMNS} FROM sessions \
+                    WHERE user_id = ?1 ORDER BY id"
+                ),
+            )?
+                .query_map(params![user_id], session_from_row)?
+                .collect()
+        })
+    }
+
+    async fn update_session(
+        &self, session: Session,
+    ) -> StorageResult<Option<Session>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "UPDATE sessions SET user_id = ?2, expires_at = ?3, last_seen_at = ?4 \
+                    WHERE id = ?1 RETURNING {SESSION_COLUMNS}"
+                ),
+                params![
+                    session.id,
+                    session.user_id,
+                    session.expires_at as i64,
+                    session.last_seen_at,
+                ],
+                session_from_row,
+            ).optional()
+        })
+    }
+
+    async fn delete_session(
+        &self, session_id: u32,
+    ) -> StorageResult<Option<Session>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "DELETE FROM sessions WHERE id = ?1 RETURNING {SESSION_COLUMNS}"
+                ),
+                params![session_id],
+                session_from_row,
+            ).optional()
+        })
+    }
+}
+
+#[async_trait]
+impl RefreshTokensRepository for SqliteStorage {
+    async fn insert_refresh_token(
+        &self, new_refresh_token: NewRefreshToken,
+    ) -> StorageResult<RefreshToken> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "INSERT INTO refresh_tokens (session_id, token_hash, created_at) \
+                    VALUES (?1, ?2, ?3) RETURNING {REFRESH_TOKEN_COLUMNS}"
+                ),
+                params![
+                    new_refresh_token.session_id,
+                    new_refresh_token.token_hash,
+                    new_refresh_token.created_at,
+                ],
+                refresh_token_from_row,
+            )
+        })
+    }
+
+    async fn refresh_token_by_hash(
+        &self, token_hash: &str,
+    ) -> StorageResult<Option<RefreshToken>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "SELECT {REFRESH_TOKEN_COLUMNS} FROM refresh_tokens \
+                    WHERE token_hash = ?1"
+                ),
+                params![token_hash],
+                refresh_token_from_row,
+            ).optional()
+        })
+    }
+
+    async fn refresh_tokens_by_session(
+        &self, session_id: u32,
+    ) -> StorageResult<Vec<RefreshToken>> {
+        self.with_connection(|connection| {
+            connection.prepare(
+                &format!(
+                    "SELECT {REFRESH_TOKEN_COLUMNS} FROM refresh_tokens \
+                    WHERE session_id = ?1 ORDER BY id"
+                ),
+            )?
+                .query_map(params![session_id], refresh_token_from_row)?
+                .collect()
+        })
+    }
+
+    async fn use_refresh_token(
+        &self, refresh_token_id: u64, used_at: DateTime<Utc>,
+    ) -> StorageResult<Option<RefreshToken>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "UPDATE refresh_tokens SET used_at = ?2 \
+                    WHERE id = ?1 AND used_at IS NULL RETURNING {REFRESH_TOKEN_COLUMNS}"
+                ),
+                params![refresh_token_id, used_at],
+                refresh_token_from_row,
+            ).optional()
+        })
+    }
+
+    async fn delete_refresh_token(
+        &self, refresh_token_id: u64,
+    ) -> StorageResult<Option<RefreshToken>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "DELETE FROM refresh_tokens WHERE id = ?1 RETURNING {REFRESH_TOKEN_COLUMNS}"
+                ),
+                params![refresh_token_id],
+                refresh_token_from_row,
+            ).optional()
+        })
+    }
+}
+
+#[async_trait]
+impl SharesRepository for SqliteStorage {
+    async fn insert_share(&self, new_share: NewNoteShare) -> StorageResult<NoteShare> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "INSERT INTO note_shares \
+                    (note_id, owner_id, user_id, permission, created_at) \
+                    VALUES (?1, ?2, ?3, ?4, ?5) RETURNING {SHARE_COLUMNS}"
+                ),
+                params![
+                    new_share.note_id,
+                    new_share.owner_id,
+                    new_share.user_id,
+                    new_share.permission.as_ref(),
+                    new_share.created_at,
+                ],
+                share_from_row,
+            )
+        })
+    }
+
+    async fn share_by_id(&self, share_id: u64) -> StorageResult<Option<NoteShare>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!("SELECT {SHARE_COLUMNS} FROM note_shares WHERE id = ?1"),
+                params![share_id],
+                share_from_row,
+            ).optional()
+        })
+    }
+
+    async fn shares_by_note(&self, note_id: u64) -> StorageResult<Vec<NoteShare>> {
+        self.with_connection(|connection| {
+            connection.prepare(
+                &format!(
+                    "SELECT {SHARE_COLUMNS} FROM note_shares \
+                    WHERE note_id = ?1 ORDER BY id"
+                ),
+            )?
+                .query_map(params![note_id], share_from_row)?
+                .collect()
+        })
+    }
+
+    async fn shares_by_user(&self, user_id: u32) -> StorageResult<Vec<NoteShare>> {
+        self.with_connection(|connection| {
+            connection.prepare(
+                &format!(
+                    "SELECT {SHARE_COLUMNS} FROM note_shares \
+                    WHERE user_id = ?1 ORDER BY id"
+                ),
+            )?
+                .query_map(params![user_id], share_from_row)?
+                .collect()
+        })
+    }
+
+    async fn update_share(&self, share: NoteShare) -> StorageResult<Option<NoteShare>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "UPDATE note_shares SET permission = ?2 \
+                    WHERE id = ?1 RETURNING {SHARE_COLUMNS}"
+                ),
+                params![share.id, share.permission.as_ref()],
+                share_from_row,
+            ).optional()
+        })
+    }
+
+    async fn delete_share(&self, share_id: u64) -> StorageResult<Option<NoteShare>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "DELETE FROM note_shares WHERE id = ?1 RETURNING {SHARE_COLUMNS}"
+                ),
+                params![share_id],
+                share_from_row,
+            ).optional()
+        })
+    }
+}
+
+#[async_trait]
+impl ShareEventsRepository for SqliteStorage {
+    async fn insert_share_event(
+        &self, new_event: NewShareEvent,
+    ) -> StorageResult<ShareEvent> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "INSERT INTO share_events \
+                    (note_id, actor_id, user_id, action, permission, occurred_at) \
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6) RETURNING {SHARE_EVENT_COLUMNS}"
+                ),
+                params![
+                    new_event.note_id,
+                    new_event.actor_id,
+                    new_event.user_id,
+                    new_event.action.as_ref(),
+                    new_event.permission.as_ref(),
+                    new_event.occurred_at,
+                ],
+                share_event_from_row,
+            )
+        })
+    }
+
+    async fn share_events_by_note(&self, note_id: u64) -> StorageResult<Vec<ShareEvent>> {
+        self.with_connection(|connection| {
+            connection.prepare(
+                &format!(
+                    "SELECT {SHARE_EVENT_COLUMNS} FROM share_events \
+                    WHERE note_id = ?1 ORDER BY id"
+                ),
+            )?
+                .query_map(params![note_id], share_event_from_row)?
+                .collect()
+        })
+    }
+}
+
+#[async_trait]
+impl LinksRepository for SqliteStorage {
+    async fn insert_link(&self, new_link: NewNoteLink) -> StorageResult<NoteLink> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!(
+                    "INSERT INTO note_links \
+                    (note_id, owner_id, token, password_hash, expires_at, created_at) \
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6) RETURNING {LINK_COLUMNS}"
+                ),
+                params![
+                    new_link.note_id,
+                    new_link.owner_id,
+                    new_link.token,
+                    new_link.password_hash,
+                    new_link.expires_at,
+                    new_link.created_at,
+                ],
+                link_from_row,
+            )
+        })
+    }
+
+    async fn link_by_id(&self, link_id: u64) -> StorageResult<Option<NoteLink>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!("SELECT {LINK_COLUMNS} FROM note_links WHERE id = ?1"),
+                params![link_id],
+                link_from_row,
+            ).optional()
+        })
+    }
+
+    async fn link_by_token(&self, token: &str) -> StorageResult<Option<NoteLink>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!("SELECT {LINK_COLUMNS} FROM note_links WHERE token = ?1"),
+                params![token],
+                link_from_row,
+            ).optional()
+        })
+    }
+
+    async fn links_by_note(&self, note_id: u64) -> StorageResult<Vec<NoteLink>> {
+        self.with_connection(|connection| {
+            connection.prepare(
+                &format!(
+                    "SELECT {LINK_COLUMNS} FROM note_links \
+                    WHERE note_id = ?1 ORDER BY id"
+                ),
+            )?
+                .query_map(params![note_id], link_from_row)?
+                .collect()
+        })
+    }
+
+    async fn delete_link(&self, link_id: u64) -> StorageResult<Option<NoteLink>> {
+        self.with_connection(|connection| {
+            connection.query_row(
+                &format!("DELETE FROM note_links WHERE id = ?1 RETURNING {LINK_COLUMNS}"),
+                params![link_id],
+                link_from_row,
+            ).optional()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::model::notes::notes_query::{NotesPage, NotesQuery};
+    use crate::model::storage::memory::InMemoryNotesRepository;
+
+    const CREATOR_ID: u32 = 1;
+    const OTHER_CREATOR_ID: u32 = 2;
+
+    /// The same notes stored in SQLite and in memory, the reference
+    /// the SQL filter must agree with.
+    struct Backends {
+        sqlite: SqliteStorage,
+        memory: InMemoryNotesRepository,
+        start: DateTime<Utc>,
+    }
+
+    async fn backends() -> Backends {
+        let backends = Backends {
+            sqlite: SqliteStorage::open(":memory:").unwrap(),
+            memory: InMemoryNotesRepository::default(),
+            start: Utc::now(),
+        };
+
+        let notes: &[(&str, &[&str])] = &[
+            ("100% done", &["work"]),
+            ("100 percent", &["work", "home"]),
+            ("snake_case", &["home"]),
+            ("snakeXcase", &[]),
+            ("Ёжик в тумане", &["home", "film"]),
+            ("ёЖИК", &["film"]),
+            ("Plain", &["work"]),
+            ("plain", &[]),
+        ];
+
+        for (index, (title, tags)) in notes.iter().enumerate() {
+            let note = backends.insert(CREATOR_ID, title, tags, index).await;
+
+            //Every other note is edited a day later, in reverse order
+            if index % 2 == 0 {
+                backends.update(Note {
+                    updated_at: backends.start + Duration::days(1)
+                        - Duration::minutes(index as i64),
+                    ..note
+                }).await;
+            }
+        }
+
+        let trashed = backends.insert(CREATOR_ID, "100% trashed", &["work"], notes.len()).await;
+        backends.update(Note { deleted_at: Some(backends.start), ..trashed }).await;
+
+        backends.insert(OTHER_CREATOR_ID, "100% other", &["work"], notes.len()).await;
+
+        backends
+    }
+
+    impl Backends {
+        async fn insert(
+            &self, creator_id: u32, title: &str, tags: &[&str], minutes: usize,
+        ) -> Note {
+            let new_note = || NewNote {
+                creator_id,
+                title: title.to_string(),
+                body: String::new(),
+                created_at: self.start + Duration::minutes(minutes as i64),
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                notebook_id: None,
+            };
+
+            self.memory.insert_note(new_note()).await.unwrap();
+            self.sqlite.insert_note(new_note()).await.unwrap()
+        }
+
+        async fn update(&self, note: Note) {
+            let note = Note { version: note.version + 1, ..note };
+
+            //SQLite ids start at one, in-memory ones at zero
+            self.memory.update_note(Note { id: note.id - 1, ..note.clone() }).await.unwrap();
+            self.sqlite.update_note(note).await.unwrap();
+        }
+
+        /// Titles of all pages of `limit` notes, checking both
+        /// backends return the same pages.
+        async fn titles(
+            &self, limit: usize, query: impl Fn(NotesQuery) -> NotesQuery,
+        ) -> Vec<String> {
+            let mut titles = Vec::new();
+            let (mut sqlite_cursor, mut memory_cursor) = (None, None);
+
+            loop {
+                let sqlite_page = page(&self.sqlite, query(base_query(limit, sqlite_cursor))).await;
+                let memory_page = page(&self.memory, query(base_query(limit, memory_cursor))).await;
+
+                assert_eq!(page_titles(&sqlite_page), page_titles(&memory_page));
+                assert_eq!(sqlite_page.total, memory_page.total);
+
+                titles.extend(page_titles(&sqlite_page));
+
+                match (sqlite_page.next_cursor, memory_page.next_cursor) {
+                    (Some(sqlite), Some(memory)) => {
+                        sqlite_cursor = Some(sqlite);
+                        memory_cursor = Some(memory);
+                    }
+                    (None, None) => return titles,
+                    _ => panic!("only one backend has a next page"),
+                }
+            }
+        }
+    }
+
+    fn base_query(limit: usize, cursor: Option<String>) -> NotesQuery {
+        NotesQuery {
+            cursor,
+            limit: Some(limit),
+            sort: NotesSort::Created,
+            order: SortOrder::Asc,
+            title: None,
+            created_from: None,
+            created_to: None,
+            updated_from: None,
+            updated_to: None,
+            tag: None,
+            tag_mode: TagMode::And,
+        }
+    }
+
+    async fn page(repository: &dyn NotesRepository, query: NotesQuery) -> NotesPage {
+        let filter = query.filter(CREATOR_ID).unwrap();
+
+        filter.page(repository.notes_by_filter(&filter).await.unwrap())
+    }
+
+    fn page_titles(page: &NotesPage) -> Vec<String> {
+        page.notes.iter().map(|note| note.title.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn title_wildcards_match_literally() {
+        let backends = backends().await;
+
+        let title = |title: &str| {
+            let title = Some(title.to_string());
+            move |query| NotesQuery { title: title.clone(), ..query }
+        };
+
+        assert_eq!(backends.titles(10, title("%")).await, ["100% done"]);
+        assert_eq!(backends.titles(10, title("0%")).await, ["100% done"]);
+        assert_eq!(backends.titles(10, title("_")).await, ["snake_case"]);
+        assert_eq!(backends.titles(10, title("e_c")).await, ["snake_case"]);
+        assert_eq!(backends.titles(10, title("ЁЖИК")).await, ["Ёжик в тумане", "ёЖИК"]);
+        assert_eq!(backends.titles(10, title("PLAIN")).await, ["Plain", "plain"]);
+        assert!(backends.titles(10, title("%%")).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn date_ranges_include_start_and_exclude_end() {
+        let backends = backends().await;
+        let at = |minutes: i64| Some(backends.start + Duration::minutes(minutes));
+
+        assert_eq!(
+            backends.titles(2, |query| NotesQuery {
+                created_from: at(2),
+                created_to: at(5),
+                ..query
+            }).await,
+            ["snake_case", "snakeXcase", "Ёжик в тумане"],
+        );
+
+        //Notes 0, 2, 4 and 6 were edited at a day minus their index in minutes
+        assert_eq!(
+            backends.titles(2, |query| NotesQuery {
+                sort: NotesSort::Updated,
+                updated_from: Some(backends.start + Duration::days(1) - Duration::minutes(4)),
+                updated_to: Some(backends.start + Duration::days(1)),
+                ..query
+            }).await,
+            ["Ёжик в тумане", "snake_case"],
+        );
+
+        assert!(backends.titles(2, |query| NotesQuery {
+            created_from: at(3),
+            created_to: at(3),
+            ..query
+        }).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn tags_match_all_or_any() {
+        let backends = backends().await;
+
+        let tags = |tag: &str, tag_mode: TagMode| {
+            let tag = Some(tag.to_string());
+            move |query| NotesQuery { tag: tag.clone(), tag_mode, ..query }
+        };
+
+        assert_eq!(
+            backends.titles(2, tags("work,home", TagMode::And)).await,
+            ["100 percent"],
+        );
+        assert_eq!(
+            backends.titles(2, tags("home,home", TagMode::And)).await,
+            ["100 percent", "snake_case", "Ёжик в тумане"],
+        );
+        assert_eq!(
+            backends.titles(2, tags("work,film", TagMode::Or)).await,
+            ["100% done", "100 percent", "Ёжик в тумане", "ёЖИК", "Plain"],
+        );
+        assert!(backends.titles(2, tags("missing", TagMode::Or)).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn cursor_pages_through_every_sort_and_order() {
+        let backends = backends().await;
+
+        for sort in [NotesSort::Created, NotesSort::Updated, NotesSort::Title] {
+            for order in [SortOrder::Asc, SortOrder::Desc] {
+                let sorted = |query| NotesQuery { sort, order, ..query };
+
+                let whole = backends.titles(200, sorted).await;
+                assert_eq!(whole.len(), 8);
+
+                for limit in 1..=8 {
+                    assert_eq!(backends.titles(limit, sorted).await, whole);
+                }
+            }
+        }
+    }
+}
@@ -1,2 +1,4 @@
+pub mod password_hashing;
 pub mod users_models;
+pub mod users_repository;
 pub mod users_service;
\ No newline at end of file
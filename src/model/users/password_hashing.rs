@@ -0,0 +1,102 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use argon2::password_hash::{
+    PasswordHash,
+    PasswordHasher,
+    PasswordVerifier,
+    SaltString,
+};
+use rand::rngs::OsRng;
+use subtle::ConstantTimeEq;
+
+use crate::settings::Password;
+
+/// Argon2id hashing of user passwords with the parameters from `Settings`.
+#[derive(Clone)]
+pub struct PasswordHashing {
+    argon2: Argon2<'static>,
+    dummy_hash: String,
+}
+
+pub enum PasswordCheck {
+    Invalid,
+    Valid { needs_rehash: bool },
+}
+
+impl PasswordHashing {
+    pub fn new(password: &Password) -> Self {
+        let params = Params::new(
+            password.memory_kib,
+            password.iterations,
+            password.parallelism,
+            None,
+        ).expect("Password hashing parameters must be valid");
+
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        let dummy_hash = hash_password(&argon2, "dummy password")
+            .expect("Password hashing must work");
+
+        Self { argon2, dummy_hash }
+    }
+}
+
+impl PasswordHashing {
+    /// Hashes the password with a fresh random salt on the blocking pool.
+    pub async fn hash(&self, password: String) -> Option<String> {
+        let argon2 = self.argon2.clone();
+
+        tokio::task::spawn_blocking(move || hash_password(&argon2, &password))
+            .await.ok().flatten()
+    }
+
+    /// Verifies the password against a stored hash. Hashes created with
+    /// other parameters and legacy plaintext passwords are reported as
+    /// valid but needing a rehash.
+    pub async fn verify(&self, password: String, stored: String) -> PasswordCheck {
+        let argon2 = self.argon2.clone();
+
+        tokio::task::spawn_blocking(move || verify_password(&argon2, &password, &stored))
+            .await.unwrap_or(PasswordCheck::Invalid)
+    }
+
+    /// Burns the same amount of work as a real verification, so a login
+    /// for an unknown nickname cannot be told apart by its timing.
+    pub async fn verify_dummy(&self, password: String) {
+        self.verify(password, self.dummy_hash.clone()).await;
+    }
+}
+
+fn hash_password(argon2: &Argon2, password: &str) -> Option<String> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    argon2.hash_password(password.as_bytes(), &salt)
+        .ok()
+        .map(|hash| hash.to_string())
+}
+
+fn verify_password(argon2: &Argon2, password: &str, stored: &str) -> PasswordCheck {
+    let Ok(hash) = PasswordHash::new(stored) else {
+        let is_legacy_match = password.as_bytes().ct_eq(stored.as_bytes()).into();
+
+        return if is_legacy_match {
+            PasswordCheck::Valid { needs_rehash: true }
+        } else {
+            PasswordCheck::Invalid
+        };
+    };
+
+    if argon2.verify_password(password.as_bytes(), &hash).is_err() {
+        return PasswordCheck::Invalid;
+    }
+
+    let current_params = argon2.params();
+    let is_up_to_date = hash.algorithm == Algorithm::Argon2id.ident()
+        && hash.version == Some(Version::V0x13.into())
+        && Params::try_from(&hash).is_ok_and(|params| {
+            params.m_cost() == current_params.m_cost()
+                && params.t_cost() == current_params.t_cost()
+                && params.p_cost() == current_params.p_cost()
+        });
+
+    PasswordCheck::Valid { needs_rehash: !is_up_to_date }
+}
@@ -1,11 +1,45 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use strum_macros::AsRefStr;
 
-#[derive(Clone, Serialize)]
+use crate::error::{Error, FieldError, Result, UserError};
+
+/// Stored user with credentials. Never returned to clients,
+/// use `UserProfile` for responses.
+#[derive(Clone)]
 pub struct User {
     pub id: u32,
     pub name: String,
     pub nickname: String,
     pub password: String,
+    pub role: UserRole,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct NewUser {
+    pub name: String,
+    pub nickname: String,
+    pub password: String,
+    pub role: UserRole,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, AsRefStr)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum UserRole {
+    #[default]
+    User,
+    Admin,
+}
+
+#[derive(Serialize)]
+pub struct UserProfile {
+    pub id: u32,
+    pub name: String,
+    pub nickname: String,
+    pub role: UserRole,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Deserialize)]
@@ -21,10 +55,11 @@ pub struct UserLogin {
     pub password: String,
 }
 
+/// Changes of an account. The current `password` is required
+/// for changing the nickname or the password of one's own account.
 #[derive(Deserialize)]
 pub struct UserEdit {
-    pub id: u32,
-    pub password: String,
+    pub password: Option<String>,
     pub name: Option<String>,
     pub nickname: Option<String>,
     pub new_password: Option<String>,
@@ -32,6 +67,93 @@ pub struct UserEdit {
 
 #[derive(Deserialize)]
 pub struct UserDelete {
-    pub id: u32,
     pub password: String,
+}
+
+impl UserRole {
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "user" => Some(UserRole::User),
+            "admin" => Some(UserRole::Admin),
+            _ => None,
+        }
+    }
+}
+
+impl From<User> for UserProfile {
+    fn from(user: User) -> Self {
+        UserProfile {
+            id: user.id,
+            name: user.name,
+            nickname: user.nickname,
+            role: user.role,
+            created_at: user.created_at,
+        }
+    }
+}
+
+impl UserCreate {
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+
+        validate_name(&self.name, &mut errors);
+        validate_nickname(&self.nickname, &mut errors);
+        validate_password("password", &self.password, &mut errors);
+
+        into_validation_result(errors)
+    }
+}
+
+impl UserEdit {
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+
+        if let Some(name) = self.name.as_ref() {
+            validate_name(name, &mut errors);
+        }
+        if let Some(nickname) = self.nickname.as_ref() {
+            validate_nickname(nickname, &mut errors);
+        }
+        if let Some(new_password) = self.new_password.as_ref() {
+            validate_password("new_password", new_password, &mut errors);
+        }
+
+        into_validation_result(errors)
+    }
+}
+
+fn validate_name(name: &str, errors: &mut Vec<FieldError>) {
+    if name.trim().is_empty() {
+        errors.push(FieldError { field: "name", message: "Name must not be blank" });
+    }
+}
+
+fn validate_nickname(nickname: &str, errors: &mut Vec<FieldError>) {
+    if nickname.is_empty() {
+        errors.push(FieldError {
+            field: "nickname",
+            message: "Nickname must not be empty",
+        });
+    } else if nickname.chars().any(char::is_whitespace) {
+        errors.push(FieldError {
+            field: "nickname",
+            message: "Nickname must not contain whitespace",
+        });
+    }
+}
+
+fn validate_password(
+    field: &'static str, password: &str, errors: &mut Vec<FieldError>,
+) {
+    if password.is_empty() {
+        errors.push(FieldError { field, message: "Password must not be empty" });
+    }
+}
+
+fn into_validation_result(errors: Vec<FieldError>) -> Result<()> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::User(UserError::InvalidFields(errors)))
+    }
 }
\ No newline at end of file
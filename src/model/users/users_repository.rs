@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+
+use crate::error::StorageResult;
+use crate::model::users::users_models::{NewUser, User};
+
+#[async_trait]
+pub trait UsersRepository: Send + Sync {
+    /// Stores a new user, failing with `StorageError::Conflict`
+    /// if the nickname is already captured.
+    async fn insert_user(&self, new_user: NewUser) -> StorageResult<User>;
+
+    async fn user_by_id(&self, user_id: u32) -> StorageResult<Option<User>>;
+
+    async fn user_by_nickname(
+        &self, nickname: &str,
+    ) -> StorageResult<Option<User>>;
+
+    /// Replaces the stored user with the same id, failing with
+    /// `StorageError::Conflict` if the nickname is captured by another user.
+    async fn update_user(&self, user: User) -> StorageResult<Option<User>>;
+
+    async fn delete_user(&self, user_id: u32) -> StorageResult<Option<User>>;
+}
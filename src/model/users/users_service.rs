@@ -1,50 +1,100 @@
-use std::sync::{Arc, Mutex};
-use crate::model::users::users_models::{User, UserCreate, UserEdit, UserLogin};
-use crate::error::{Result, Error, UserError};
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::error::{Error, FieldError, Result, StorageError, UserError};
+use crate::log::log_layer;
+use crate::model::users::password_hashing::{PasswordCheck, PasswordHashing};
+use crate::model::users::users_models::{
+    NewUser,
+    User,
+    UserCreate,
+    UserEdit,
+    UserLogin,
+    UserRole,
+};
+use crate::model::users::users_repository::UsersRepository;
+
+const USERS_LAYER: &str = "USERS";
 
 #[derive(Clone)]
 pub struct UsersService {
-    users_collection: Arc<Mutex<Vec<Option<User>>>>,
+    repository: Arc<dyn UsersRepository>,
+    passwords: PasswordHashing,
 }
 
 impl UsersService {
-    pub fn new() -> Self {
-        Self { users_collection: Arc::default() }
+    pub fn new(repository: Arc<dyn UsersRepository>, passwords: PasswordHashing) -> Self {
+        Self { repository, passwords }
     }
 }
 
 impl UsersService {
     pub async fn create_user(&self, user_create: UserCreate) -> Result<User> {
-        let mut collection = self.users_collection.lock()
-            .map_err(|_| Error::User(UserError::RegisterFail))?;
-
-        let is_user_with_same_nickname_exists = collection.iter()
-            .any(|user_option| user_option.as_ref()
-                .is_some_and(|user|user.nickname == user_create.nickname)
-            );
-
-        if is_user_with_same_nickname_exists {
-            return Err(Error::User(UserError::RegisterFailNicknameCaptured));
-        }
+        user_create.validate()?;
 
-        let id = collection.len() as u32;
+        let password = self.passwords.hash(user_create.password).await
+            .ok_or(Error::User(UserError::RegisterFail))?;
 
-        let user = User {
-            id,
+        let new_user = NewUser {
             name: user_create.name,
             nickname: user_create.nickname,
-            password: user_create.password,
+            password,
+            role: UserRole::User,
+            created_at: Utc::now(),
         };
 
-        collection.push(Some(user.clone()));
+        self.repository.insert_user(new_user).await
+            .map_err(|error| match error {
+                StorageError::Conflict =>
+                    Error::User(UserError::RegisterFailNicknameCaptured),
+                _ => Error::User(UserError::RegisterFail),
+            })
+    }
 
-        Ok(user)
+    /// Makes administrators of the existing accounts with the given
+    /// nicknames. Only done at startup, so registering a listed nickname
+    /// later on grants nothing until the operator restarts the server.
+    pub async fn promote_admins(&self, nicknames: &[String]) -> Result<()> {
+        for nickname in nicknames {
+            let user = self.repository.user_by_nickname(nickname).await
+                .map_err(|_| Error::User(UserError::EditFail))?;
+
+            let Some(user) = user.filter(|user| user.role != UserRole::Admin) else {
+                continue;
+            };
+
+            self.repository.update_user(User { role: UserRole::Admin, ..user }).await
+                .map_err(|_| Error::User(UserError::EditFail))?;
+
+            log_layer(USERS_LAYER, format!("promoted {nickname} to admin").as_str());
+        }
+
+        Ok(())
+    }
+
+    pub async fn user(&self, user_id: u32) -> Result<User> {
+        self.repository.user_by_id(user_id).await
+            .map_err(|_| Error::User(UserError::AuthFail))?
+            .ok_or(Error::User(UserError::UserDoesNotExists))
     }
 
-    pub async fn edit_user(&self, user_edit: UserEdit) -> Result<User> {
-        let mut collection = self.users_collection.lock()
-            .map_err(|_| Error::User(UserError::EditFail))?;
+    /// Edits one's own account, the current password is verified
+    /// for nickname and password changes.
+    pub async fn edit_user(&self, user_id: u32, user_edit: UserEdit) -> Result<User> {
+        self.edit(user_id, user_edit, true).await
+    }
+
+    /// Edits any account without knowing its password.
+    pub async fn edit_user_as_admin(
+        &self, user_id: u32, user_edit: UserEdit,
+    ) -> Result<User> {
+        self.edit(user_id, user_edit, false).await
+    }
 
+    async fn edit(
+        &self, user_id: u32, user_edit: UserEdit, verify_password: bool,
+    ) -> Result<User> {
         if user_edit.name.is_none()
             && user_edit.nickname.is_none()
             && user_edit.new_password.is_none()
@@ -52,61 +102,118 @@ impl UsersService {
             return Err(Error::User(UserError::EmptyFieldToEdit));
         }
 
-        if let Some(new_nickname) = user_edit.nickname.as_ref() {
-            let is_new_nickname_captured = collection.iter()
-                .any(|user_option| user_option.as_ref()
-                    .is_some_and(|user| user.nickname == *new_nickname
-                        && user.id != user_edit.id
-                    )
-                );
-
-            if is_new_nickname_captured {
-                return Err(Error::User(UserError::EditFailNicknameCaptured));
-            }
-        }
+        user_edit.validate()?;
 
-        let user = collection.get_mut(user_edit.id as usize)
-            .and_then(|user| user.take())
+        let user = self.repository.user_by_id(user_id).await
+            .map_err(|_| Error::User(UserError::EditFail))?
             .ok_or(Error::User(UserError::UserDoesNotExists))?;
 
+        let is_sensitive_change = user_edit.nickname.is_some()
+            || user_edit.new_password.is_some();
+
+        if verify_password && is_sensitive_change {
+            self.verify_current_password(&user, user_edit.password).await?;
+        }
+
+        let password = match user_edit.new_password {
+            Some(new_password) => self.passwords.hash(new_password).await
+                .ok_or(Error::User(UserError::EditFail))?,
+            None => user.password,
+        };
+
         let edited_user = User {
             name: user_edit.name.unwrap_or(user.name),
             nickname: user_edit.nickname.unwrap_or(user.nickname),
-            password: user_edit.new_password.unwrap_or(user_edit.password),
+            password,
             ..user
         };
 
-        collection[edited_user.id as usize] = Some(edited_user.clone());
-
-        Ok(edited_user)
+        self.repository.update_user(edited_user).await
+            .map_err(|error| match error {
+                StorageError::Conflict =>
+                    Error::User(UserError::EditFailNicknameCaptured),
+                _ => Error::User(UserError::EditFail),
+            })?
+            .ok_or(Error::User(UserError::UserDoesNotExists))
     }
 
-    pub async fn delete_user(&self, user_id: u32) -> Result<User> {
-        let mut collection = self.users_collection.lock()
-            .map_err(|_| Error::User(UserError::DeleteFail))?;
+    /// Account its user is about to delete, once the current password
+    /// is verified. Checked before anything of the account is purged.
+    pub async fn user_to_delete(&self, user_id: u32, password: String) -> Result<User> {
+        let user = self.user_to_delete_as_admin(user_id).await?;
 
-        let user = collection.get_mut(user_id as usize)
-            .and_then(|user| user.take());
+        self.verify_current_password(&user, Some(password)).await?;
+
+        Ok(user)
+    }
 
-        user.ok_or(Error::User(UserError::UserDoesNotExists))
+    /// Account an administrator is about to delete.
+    pub async fn user_to_delete_as_admin(&self, user_id: u32) -> Result<User> {
+        self.repository.user_by_id(user_id).await
+            .map_err(|_| Error::User(UserError::DeleteFail))?
+            .ok_or(Error::User(UserError::UserDoesNotExists))
+    }
+
+    /// Deletes the account itself. Goes last, after its sessions and
+    /// content, so a purge failing halfway is retried by deleting again.
+    pub async fn delete_user(&self, user_id: u32) -> Result<User> {
+        self.repository.delete_user(user_id).await
+            .map_err(|_| Error::User(UserError::DeleteFail))?
+            .ok_or(Error::User(UserError::UserDoesNotExists))
     }
 
     pub async fn login(&self, user_login: UserLogin) -> Result<User> {
-        let collection = self.users_collection.lock()
+        let user = self.repository.user_by_nickname(&user_login.nickname).await
             .map_err(|_| Error::User(UserError::LoginFail))?;
 
-        let user = collection.iter()
-            .find(|user_option| user_option.as_ref()
-                .is_some_and(|user| user.nickname == user_login.nickname)
-            )
-            .ok_or(Error::User(UserError::UserDoesNotExists))?
-            .as_ref()
-            .ok_or(Error::User(UserError::UserDoesNotExists))?;
+        // Unknown nicknames take as long and fail the same way as wrong
+        // passwords, so a login does not tell which nicknames exist
+        let Some(user) = user else {
+            self.passwords.verify_dummy(user_login.password).await;
+            return Err(Error::User(UserError::LoginFailInvalidParams));
+        };
 
-        if user.password == user_login.password {
-            Ok(user.clone())
-        } else {
-            Err(Error::User(UserError::LoginFailInvalidParams))
+        let check = self.passwords
+            .verify(user_login.password.clone(), user.password.clone())
+            .await;
+
+        match check {
+            PasswordCheck::Invalid =>
+                Err(Error::User(UserError::LoginFailInvalidParams)),
+            PasswordCheck::Valid { needs_rehash: false } => Ok(user),
+            PasswordCheck::Valid { needs_rehash: true } =>
+                Ok(self.rehash_password(user, user_login.password).await),
+        }
+    }
+
+    async fn verify_current_password(
+        &self, user: &User, password: Option<String>,
+    ) -> Result<()> {
+        let password = password.ok_or_else(|| Error::User(
+            UserError::InvalidFields(vec![FieldError {
+                field: "password",
+                message: "Current password is required for this change",
+            }])
+        ))?;
+
+        match self.passwords.verify(password, user.password.clone()).await {
+            PasswordCheck::Invalid => Err(Error::User(UserError::WrongPassword)),
+            PasswordCheck::Valid { .. } => Ok(()),
         }
     }
-}
\ No newline at end of file
+
+    /// Stores the password hashed with the current parameters. A failed
+    /// rehash is not a reason to reject an otherwise valid login.
+    async fn rehash_password(&self, user: User, password: String) -> User {
+        let Some(password) = self.passwords.hash(password).await else {
+            return user;
+        };
+
+        let rehashed_user = User { password, ..user.clone() };
+
+        match self.repository.update_user(rehashed_user).await {
+            Ok(Some(rehashed_user)) => rehashed_user,
+            _ => user,
+        }
+    }
+}
@@ -4,18 +4,81 @@ use config::{Config, ConfigError, Map, Value};
 pub struct Server {
     pub host: String,
     pub port: u16,
+    pub legacy_routes: bool,
 }
 
 #[derive(Clone)]
 pub struct Jwt {
     pub secret: String,
-    pub validity_days: u16
+    //Days a session and its refresh tokens stay valid
+    pub validity_days: u16,
+    //Minutes an access token stays valid
+    pub access_validity_minutes: u16,
+}
+
+#[derive(Clone)]
+pub struct Password {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+#[derive(Clone)]
+pub struct Users {
+    pub admins: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct Revisions {
+    //Number of revisions kept per note, 0 keeps all of them
+    pub retention: usize,
+}
+
+#[derive(Clone)]
+pub struct Trash {
+    //Days a deleted note stays restorable
+    pub retention_days: u64,
+    //Seconds between purges of expired notes
+    pub purge_interval_seconds: u64,
+}
+
+#[derive(Clone)]
+pub struct Storage {
+    pub backend: StorageBackend,
+}
+
+#[derive(Clone, Debug)]
+pub enum StorageBackend {
+    Memory,
+    Sqlite { path: String },
+    Journal {
+        directory: String,
+        fsync: FsyncPolicy,
+        snapshot_every: u64,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub enum FsyncPolicy {
+    //Sync the journal after every appended entry
+    Always,
+
+    //Sync the journal every given number of seconds
+    Interval(u64),
+
+    //Leave syncing to the operating system
+    Never,
 }
 
 #[derive(Clone)]
 pub struct Settings {
     pub server: Server,
     pub jwt: Jwt,
+    pub password: Password,
+    pub users: Users,
+    pub revisions: Revisions,
+    pub trash: Trash,
+    pub storage: Storage,
 }
 
 impl Settings {
@@ -26,6 +89,11 @@ impl Settings {
         Ok(Self {
             server: config.get_table("server")?.into(),
             jwt: config.get_table("jwt")?.into(),
+            password: config.get_table("password")?.into(),
+            users: config.get_table("users")?.into(),
+            revisions: config.get_table("revisions")?.into(),
+            trash: config.get_table("trash")?.into(),
+            storage: config.get_table("storage")?.into(),
         })
     }
 }
@@ -43,6 +111,9 @@ impl From<Map<String, Value>> for Server {
                 .into_string().unwrap(),
             port: map.remove("port").expect("Server port must be set")
                 .into_uint().unwrap() as u16,
+            legacy_routes: map.remove("legacy_routes")
+                .expect("Server legacy routes switch must be set")
+                .into_bool().unwrap(),
         }
     }
 }
@@ -54,8 +125,169 @@ impl From<Map<String, Value>> for Jwt {
                 .expect("JWT secret must be set")
                 .into_string().unwrap(),
             validity_days: map.remove("validity_days")
-                .expect("JWT secret must be set")
-                .into_uint().unwrap() as u16
+                .expect("JWT validity must be set")
+                .into_uint().unwrap() as u16,
+            access_validity_minutes: map.remove("access_validity_minutes")
+                .expect("JWT access token validity must be set")
+                .into_uint().unwrap() as u16,
+        }
+    }
+}
+
+impl From<Map<String, Value>> for Password {
+    fn from(mut map: Map<String, Value>) -> Self {
+        Password {
+            memory_kib: map.remove("memory_kib")
+                .expect("Password hashing memory must be set")
+                .into_uint().unwrap() as u32,
+            iterations: map.remove("iterations")
+                .expect("Password hashing iterations must be set")
+                .into_uint().unwrap() as u32,
+            parallelism: map.remove("parallelism")
+                .expect("Password hashing parallelism must be set")
+                .into_uint().unwrap() as u32,
         }
     }
-}
\ No newline at end of file
+}
+
+impl From<Map<String, Value>> for Users {
+    fn from(mut map: Map<String, Value>) -> Self {
+        Users {
+            admins: map.remove("admins")
+                .expect("Users admins must be set")
+                .into_array().unwrap()
+                .into_iter()
+                .map(|admin| admin.into_string().unwrap())
+                .collect(),
+        }
+    }
+}
+
+impl From<Map<String, Value>> for Revisions {
+    fn from(mut map: Map<String, Value>) -> Self {
+        Revisions {
+            retention: map.remove("retention")
+                .expect("Revisions retention must be set")
+                .into_uint().unwrap() as usize,
+        }
+    }
+}
+
+impl From<Map<String, Value>> for Trash {
+    fn from(mut map: Map<String, Value>) -> Self {
+        let purge_interval_seconds = map.remove("purge_interval_seconds")
+            .expect("Trash purge interval must be set")
+            .into_uint().unwrap();
+
+        //A zero period would make the purge timer panic
+        assert!(
+            purge_interval_seconds > 0,
+            "Trash purge interval must be at least one second"
+        );
+
+        Trash {
+            retention_days: map.remove("retention_days")
+                .expect("Trash retention must be set")
+                .into_uint().unwrap(),
+            purge_interval_seconds,
+        }
+    }
+}
+
+impl From<Map<String, Value>> for Storage {
+    fn from(mut map: Map<String, Value>) -> Self {
+        let backend = map.remove("backend")
+            .expect("Storage backend must be set")
+            .into_string().unwrap();
+
+        Storage {
+            backend: match backend.as_str() {
+                "memory" => StorageBackend::Memory,
+                "sqlite" => StorageBackend::Sqlite {
+                    path: map.remove("path")
+                        .expect("SQLite storage path must be set")
+                        .into_string().unwrap(),
+                },
+                "journal" => StorageBackend::Journal {
+                    directory: map.remove("directory")
+                        .expect("Journal storage directory must be set")
+                        .into_string().unwrap(),
+                    fsync: fsync_policy(&mut map),
+                    snapshot_every: map.remove("snapshot_every")
+                        .expect("Journal snapshot frequency must be set")
+                        .into_uint().unwrap(),
+                },
+                _ => panic!("Unknown storage backend: {backend}"),
+            },
+        }
+    }
+}
+
+fn fsync_policy(map: &mut Map<String, Value>) -> FsyncPolicy {
+    let fsync = map.remove("fsync")
+        .expect("Journal fsync policy must be set")
+        .into_string().unwrap();
+
+    match fsync.as_str() {
+        "always" => FsyncPolicy::Always,
+        "interval" => {
+            let seconds = map.remove("fsync_interval_seconds")
+                .expect("Journal fsync interval must be set")
+                .into_uint().unwrap();
+
+            //A zero period would make the sync timer panic
+            assert!(seconds > 0, "Journal fsync interval must be at least one second");
+
+            FsyncPolicy::Interval(seconds)
+        },
+        "never" => FsyncPolicy::Never,
+        _ => panic!("Unknown fsync policy: {fsync}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trash(purge_interval_seconds: u64) -> Trash {
+        Map::from([
+            ("retention_days".to_string(), Value::from(30u64)),
+            ("purge_interval_seconds".to_string(), Value::from(purge_interval_seconds)),
+        ]).into()
+    }
+
+    fn journal(fsync_interval_seconds: u64) -> Storage {
+        Map::from([
+            ("backend".to_string(), Value::from("journal")),
+            ("directory".to_string(), Value::from("data")),
+            ("fsync".to_string(), Value::from("interval")),
+            ("fsync_interval_seconds".to_string(), Value::from(fsync_interval_seconds)),
+            ("snapshot_every".to_string(), Value::from(1000u64)),
+        ]).into()
+    }
+
+    #[test]
+    fn trash_keeps_positive_purge_interval() {
+        assert_eq!(trash(60).purge_interval_seconds, 60);
+    }
+
+    #[test]
+    #[should_panic(expected = "Trash purge interval must be at least one second")]
+    fn trash_rejects_zero_purge_interval() {
+        trash(0);
+    }
+
+    #[test]
+    fn journal_keeps_positive_fsync_interval() {
+        assert!(matches!(
+            journal(5).backend,
+            StorageBackend::Journal { fsync: FsyncPolicy::Interval(5), .. },
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Journal fsync interval must be at least one second")]
+    fn journal_rejects_zero_fsync_interval() {
+        journal(0);
+    }
+}
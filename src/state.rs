@@ -1,3 +1,4 @@
+use crate::error::Result;
 use crate::model::database::Database;
 use crate::settings::Settings;
 use crate::web::jwt_controller::JWTController;
@@ -10,13 +11,13 @@ pub struct ApplicationState {
 }
 
 impl ApplicationState {
-    pub fn new() -> Self {
+    pub async fn new() -> Result<Self> {
         let settings = Settings::new().unwrap();
 
-        Self {
-            database: Database::new(),
+        Ok(Self {
+            database: Database::new(&settings).await?,
             jwt: JWTController::new(&settings.jwt),
             settings,
-        }
+        })
     }
 }
\ No newline at end of file
@@ -1,60 +1,219 @@
+use std::net::SocketAddr;
+
 use axum::async_trait;
 use axum::body::Body;
-use axum::extract::{FromRequestParts, State};
-use axum::http::{header, Request};
+use axum::extract::{ConnectInfo, FromRequestParts, State};
+use axum::http::{header, Request, StatusCode};
 use axum::http::request::Parts;
 use axum::middleware::Next;
-use axum::response::Response;
-use axum_extra::extract::cookie::Cookie;
+use axum::Json;
+use axum::response::{IntoResponse, Response};
+use axum_extra::extract::cookie::{Cookie, SameSite};
 use axum_extra::extract::CookieJar;
-use chrono::Utc;
+use chrono::{Duration, Utc};
 
 use crate::context::AuthTokenContext;
-use crate::error::{Error, Result, UserError};
+use crate::error::{Error, Result, SessionError, UserError};
 use crate::log::log_layer;
+use crate::model::sessions::sessions_models::{Session, SessionClient, SessionDetails};
 use crate::state::ApplicationState;
 use crate::web::jwt_controller::TokenClaims;
 
 const AUTH_TOKEN: &str = "auth-token";
+const REFRESH_TOKEN: &str = "refresh-token";
+//Refresh tokens are only sent to the routes trading and ending them
+const REFRESH_TOKEN_PATH: &str = "/api/v1/sessions";
+const DEVICE_LABEL: &str = "x-device-label";
+
+const MAX_DEVICE_LABEL_LENGTH: usize = 64;
+const MAX_USER_AGENT_LENGTH: usize = 256;
 
 const AUTH_MIDDLEWARE: &str = "AUTH_MIDDLEWARE";
 
+/// Starts a session for the user a handler has put into the response,
+/// remembering the client the login came from.
 pub async fn set_auth_token_middleware(
     State(state): State<ApplicationState>,
-    mut response: Response,
+    client: SessionClient,
+    request: Request<Body>,
+    next: Next,
 ) -> Result<Response> {
+    let mut response = next.run(request).await;
+
     log_layer(AUTH_MIDDLEWARE, "set_auth_token");
-    
+
+    let Some(user_id) = logged_in_user(&response)? else {
+        return Ok(response);
+    };
+
+    let session = state.database.sessions
+        .create_session(user_id, state.settings.jwt.validity_days, client)
+        .await?;
+
+    let refresh_token = state.database.sessions
+        .issue_refresh_token(&session)
+        .await?;
+
+    set_token_cookies(&state, &mut response, &session, &refresh_token)?;
+
+    log_layer(AUTH_MIDDLEWARE, format!("session {} started", session.id).as_str());
+
+    Ok(response)
+}
+
+/// Same as `set_auth_token_middleware` for the legacy routes. Their clients
+/// know nothing about refreshing, so the access token lasts as long
+/// as the session and no refresh token is issued.
+pub async fn set_legacy_auth_token_middleware(
+    State(state): State<ApplicationState>,
+    client: SessionClient,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response> {
+    let mut response = next.run(request).await;
+
+    log_layer(AUTH_MIDDLEWARE, "set_legacy_auth_token");
+
+    let Some(user_id) = logged_in_user(&response)? else {
+        return Ok(response);
+    };
+
+    let session = state.database.sessions
+        .create_session(user_id, state.settings.jwt.validity_days, client)
+        .await?;
+
+    set_access_token_cookie(&state, &mut response, &session, session.expires_at)?;
+
+    Ok(response)
+}
+
+/// User a successful handler has put into the response.
+fn logged_in_user(response: &Response) -> Result<Option<u32>> {
+    if response.extensions().get::<Error>().is_some() {
+        return Ok(None);
+    }
+
     let context = response.extensions()
         .get::<AuthTokenContext>()
         .ok_or(Error::User(UserError::AuthFail))?;
 
-    let user_id = context.user_id();
+    Ok(Some(context.user_id()))
+}
+
+/// Trades the refresh token cookie for a new access token
+/// and the next refresh token of the session.
+pub async fn refresh(state: &ApplicationState, cookies: &CookieJar) -> Result<Response> {
+    let token = cookies.get(REFRESH_TOKEN)
+        .ok_or(Error::Sessions(SessionError::RefreshTokenInvalid))?;
 
-    let session = state.database.sessions
-        .create_or_update_session(user_id, state.settings.jwt.validity_days)
+    let (session, refresh_token) = state.database.sessions
+        .refresh(token.value())
         .await?;
 
+    let mut response = Json(SessionDetails::new(session.clone(), Some(session.id)))
+        .into_response();
+
+    set_token_cookies(state, &mut response, &session, &refresh_token)?;
+
+    Ok(response)
+}
+
+/// Ends the session of the request's tokens and expires the cookies.
+/// Missing, expired and forged tokens only get the cookies expired.
+pub async fn logout(state: &ApplicationState, cookies: &CookieJar) -> Result<Response> {
+    let claims = cookies.get(AUTH_TOKEN)
+        .and_then(|cookie| state.jwt.get_claims_from_token(cookie.value()).ok());
+
+    if let Some(claims) = claims {
+        state.database.sessions
+            .end_session(claims.sid, claims.sub)
+            .await?;
+    }
+
+    if let Some(refresh_token) = cookies.get(REFRESH_TOKEN) {
+        state.database.sessions
+            .end_session_of_refresh_token(refresh_token.value())
+            .await?;
+    }
+
+    let mut response = StatusCode::NO_CONTENT.into_response();
+
+    for cookie in [
+        expired_cookie(AUTH_TOKEN, "/"),
+        expired_cookie(REFRESH_TOKEN, REFRESH_TOKEN_PATH),
+    ] {
+        response.headers_mut().append(
+            header::SET_COOKIE,
+            cookie.to_string().parse().unwrap(),
+        );
+    }
+
+    Ok(response)
+}
+
+/// Short-lived access token, which never outlives its session,
+/// and the refresh token renewing it.
+fn set_token_cookies(
+    state: &ApplicationState,
+    response: &mut Response,
+    session: &Session,
+    refresh_token: &str,
+) -> Result<()> {
+    let access_validity = Duration::minutes(state.settings.jwt.access_validity_minutes as i64);
+    let access_expires_at = (Utc::now() + access_validity).timestamp() as usize;
+
+    set_access_token_cookie(state, response, session, access_expires_at.min(session.expires_at))?;
+
+    let refresh_cookie = Cookie::build((REFRESH_TOKEN, refresh_token.to_string()))
+        .path(REFRESH_TOKEN_PATH)
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .max_age(time::Duration::days(state.settings.jwt.validity_days as i64))
+        .build();
+
+    response.headers_mut().append(
+        header::SET_COOKIE,
+        refresh_cookie.to_string().parse().unwrap(),
+    );
+
+    Ok(())
+}
+
+fn set_access_token_cookie(
+    state: &ApplicationState,
+    response: &mut Response,
+    session: &Session,
+    expires_at: usize,
+) -> Result<()> {
     let claims = TokenClaims {
-        sub: session.id,
-        exp: session.expires_at,
+        sub: session.user_id,
+        sid: session.id,
+        exp: expires_at,
     };
 
     let token = state.jwt.generate_token(&claims)?;
 
+    let max_age = expires_at.saturating_sub(Utc::now().timestamp() as usize);
+
     let cookie = Cookie::build((AUTH_TOKEN, token))
         .path("/")
-        .max_age(time::Duration::days(state.settings.jwt.validity_days as i64))
+        .max_age(time::Duration::seconds(max_age as i64))
         .build();
 
-    response.headers_mut().insert(
+    response.headers_mut().append(
         header::SET_COOKIE,
         cookie.to_string().parse().unwrap(),
     );
 
-    println!(">> {AUTH_MIDDLEWARE:<30} - setting ended auth token");
+    Ok(())
+}
 
-    Ok(response)
+fn expired_cookie<'a>(name: &'a str, path: &'a str) -> Cookie<'a> {
+    Cookie::build((name, ""))
+        .path(path)
+        .max_age(time::Duration::ZERO)
+        .expires(time::OffsetDateTime::UNIX_EPOCH)
+        .build()
 }
 
 pub async fn token_context_resolver_middleware(
@@ -81,9 +240,13 @@ pub async fn token_context_resolver_middleware(
                 Err(Error::User(UserError::AuthFail))
             } else {
                 match state.database.sessions
-                    .session_validity(claims.sub, claims.exp).await
+                    .session_validity(claims.sid, claims.sub).await
                 {
-                    Ok(user_id) => Ok(AuthTokenContext::new(user_id)),
+                    Ok(user_id) => state.database.users.user(user_id).await
+                        .map(|user| AuthTokenContext::new(user.id, user.role)
+                            .with_session(claims.sid)
+                        )
+                        .map_err(|_| Error::User(UserError::AuthFail)),
                     Err(_) => Err(Error::User(UserError::AuthFail))
                 }
             }
@@ -118,4 +281,25 @@ impl<S: Send + Sync> FromRequestParts<S> for AuthTokenContext {
             .ok_or(Error::User(UserError::AuthFail))?
             .clone()
     }
-}
\ No newline at end of file
+}
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for SessionClient {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+        let header_value = |name: &str, max_length: usize| parts.headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| value.chars().take(max_length).collect::<String>());
+
+        Ok(SessionClient {
+            device_label: header_value(DEVICE_LABEL, MAX_DEVICE_LABEL_LENGTH),
+            user_agent: header_value(header::USER_AGENT.as_str(), MAX_USER_AGENT_LENGTH),
+            ip: parts.extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(address)| address.ip().to_string()),
+        })
+    }
+}
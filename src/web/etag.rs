@@ -0,0 +1,31 @@
+use axum::http::{header, HeaderMap, HeaderValue};
+
+use crate::error::{Error, FieldError, NoteError, Result};
+
+/// Strong `ETag` of a note version, e.g. `"3"`.
+pub fn etag(version: u64) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{version}\"")).unwrap()
+}
+
+/// Version required by the `If-Match` header, `None` when the header
+/// is missing or `*`. Weak and multiple ETags are not accepted, as note
+/// versions are compared strongly and one at a time.
+pub fn if_match_version(headers: &HeaderMap) -> Result<Option<u64>> {
+    let Some(if_match) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+
+    let if_match = if_match.to_str().unwrap_or_default().trim();
+    if if_match == "*" {
+        return Ok(None);
+    }
+
+    if_match.strip_prefix('"')
+        .and_then(|version| version.strip_suffix('"'))
+        .and_then(|version| version.parse().ok())
+        .map(Some)
+        .ok_or_else(|| Error::Notes(NoteError::InvalidFields(vec![FieldError {
+            field: "If-Match",
+            message: "If-Match must be a single strong ETag of the note",
+        }])))
+}
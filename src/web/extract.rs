@@ -0,0 +1,79 @@
+use axum::extract::{FromRequest, FromRequestParts};
+use axum::extract::rejection::{FormRejection, JsonRejection, PathRejection, QueryRejection};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+
+use crate::error::{Error, FieldError, RequestError};
+
+/// `axum::Json`, rejecting with an `Error` so that a bad body
+/// is answered with a problem document.
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(Error))]
+pub struct Json<T>(pub T);
+
+/// `axum::Form`, rejecting with an `Error`.
+#[derive(FromRequest)]
+#[from_request(via(axum::Form), rejection(Error))]
+pub struct Form<T>(pub T);
+
+/// `axum::extract::Path`, rejecting with an `Error`.
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(Error))]
+pub struct Path<T>(pub T);
+
+/// `axum::extract::Query`, rejecting with an `Error`.
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(Error))]
+pub struct Query<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+impl From<JsonRejection> for Error {
+    fn from(rejection: JsonRejection) -> Self {
+        body_error(rejection.status())
+    }
+}
+
+impl From<FormRejection> for Error {
+    fn from(rejection: FormRejection) -> Self {
+        body_error(rejection.status())
+    }
+}
+
+impl From<PathRejection> for Error {
+    fn from(_: PathRejection) -> Self {
+        Error::Request(RequestError::InvalidParameters(vec![FieldError {
+            field: "path",
+            message: "Path parameters have invalid values",
+        }]))
+    }
+}
+
+impl From<QueryRejection> for Error {
+    fn from(_: QueryRejection) -> Self {
+        Error::Request(RequestError::InvalidParameters(vec![FieldError {
+            field: "query",
+            message: "Query parameters have invalid values",
+        }]))
+    }
+}
+
+fn body_error(status: StatusCode) -> Error {
+    let error = match status {
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => RequestError::UnsupportedMediaType,
+        StatusCode::PAYLOAD_TOO_LARGE => RequestError::BodyTooLarge,
+        //Well-formed body, which does not fit the expected fields
+        StatusCode::UNPROCESSABLE_ENTITY => RequestError::InvalidFields(vec![FieldError {
+            field: "body",
+            message: "Body has missing fields or fields of the wrong type",
+        }]),
+        _ => RequestError::MalformedBody,
+    };
+
+    Error::Request(error)
+}
@@ -43,5 +43,6 @@ impl JWTController {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenClaims {
     pub sub: u32, //user id
+    pub sid: u32, //session id
     pub exp: usize,
 }
\ No newline at end of file
@@ -1,30 +1,44 @@
-use axum::{Json, Router};
-use axum::http::{Method, Uri};
-use axum::middleware::map_response;
+use axum::{Extension, Router};
+use axum::http::{header, Method, StatusCode, Uri};
+use axum::middleware::{from_fn, map_response};
 use axum::response::{IntoResponse, Response};
-use serde_json::json;
 
 use crate::context::AuthTokenContext;
-use crate::error::{Error, ToClientStatusAndError};
+use crate::error::{Error, RequestError, ToClientStatusAndError};
 use crate::log::{log_layer, log_request};
 use crate::state::ApplicationState;
+use crate::web::problem::Problem;
+use crate::web::request_id::{request_id_middleware, RequestId};
 
 pub mod jwt_controller;
 
 mod routes;
 mod auth_middleware;
+mod etag;
+mod extract;
+mod problem;
+mod request_id;
 
 const MAPPER: &str = "MAPPER";
 
 pub fn routes(state: ApplicationState) -> Router {
-    Router::new()
-        .nest("/user", routes::users_routes::routes(state.clone()))
-        .nest("/notes", routes::notes_routes::routes(state.clone()))
+    let mut router = Router::new()
+        .nest("/api/v1", routes::api_v1::routes(state.clone()));
+
+    if state.settings.server.legacy_routes {
+        router = router
+            .nest("/user", routes::users_routes::routes(state.clone()))
+            .nest("/notes", routes::notes_routes::routes(state.clone()));
+    }
+
+    router
         .layer(map_response(response_mapper))
+        .layer(from_fn(request_id_middleware))
 }
 
 async fn response_mapper(
     token_context: Option<AuthTokenContext>,
+    Extension(request_id): Extension<RequestId>,
     method: Method,
     uri: Uri,
     response: Response,
@@ -37,21 +51,41 @@ async fn response_mapper(
         _ => token_context.as_ref()
     };
 
+    //The router answers unknown routes and methods without any error
+    let routing_error = match response.extensions().get::<Error>() {
+        None => routing_error(response.status()),
+        Some(_) => None,
+    };
+
     let service_error = response.extensions()
-        .get::<Error>();
+        .get::<Error>()
+        .or(routing_error.as_ref());
     let client_status_error = service_error
         .map(|error| error.client_status_and_error());
 
     let error_response = client_status_error
         .as_ref()
-        .map(|(status_code, client_error)| {
-            let client_error_body = json!(client_error.as_ref());
-            (*status_code, Json(client_error_body)).into_response()
+        .zip(service_error)
+        .map(|((status_code, client_error), service_error)| {
+            Problem::new(
+                service_error,
+                *status_code,
+                client_error,
+                request_id.as_str(),
+            ).into_response()
+        })
+        .map(|mut error_response| {
+            if let Some(allow) = response.headers().get(header::ALLOW) {
+                error_response.headers_mut().insert(header::ALLOW, allow.clone());
+            }
+
+            error_response
         });
     
     let client_error = client_status_error.unzip().1;
     
     log_request(
+        request_id.as_str(),
         method,
         uri,
         context,
@@ -60,4 +94,12 @@ async fn response_mapper(
     ).await;
     
     error_response.unwrap_or(response)
-}
\ No newline at end of file
+}
+
+fn routing_error(status_code: StatusCode) -> Option<Error> {
+    match status_code {
+        StatusCode::NOT_FOUND => Some(Error::Request(RequestError::RouteDoesNotExists)),
+        StatusCode::METHOD_NOT_ALLOWED => Some(Error::Request(RequestError::MethodNotAllowed)),
+        _ => None,
+    }
+}
@@ -0,0 +1,60 @@
+use axum::http::{header, StatusCode};
+use axum::Json;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+
+use crate::error::{ClientError, Error, FieldError, ToClientStatusAndError};
+
+const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// RFC 7807 problem details body of an error response.
+#[derive(Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    problem_type: String,
+    title: &'static str,
+    status: u16,
+    detail: &'static str,
+    code: String,
+    request_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<Vec<FieldError>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_version: Option<u64>,
+}
+
+impl Problem {
+    pub fn new(
+        error: &Error,
+        status_code: StatusCode,
+        client_error: &ClientError,
+        request_id: &str,
+    ) -> Self {
+        Problem {
+            problem_type: format!(
+                "urn:notes-server:problem:{}",
+                client_error.as_ref().to_lowercase()
+            ),
+            title: client_error.title(),
+            status: status_code.as_u16(),
+            detail: error.client_detail(),
+            code: error.code(),
+            request_id: request_id.to_string(),
+            errors: error.field_errors().map(|errors| errors.to_vec()),
+            current_version: error.current_version(),
+        }
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let status_code = StatusCode::from_u16(self.status)
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+        (
+            status_code,
+            [(header::CONTENT_TYPE, PROBLEM_CONTENT_TYPE)],
+            Json(self),
+        ).into_response()
+    }
+}
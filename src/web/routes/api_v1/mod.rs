@@ -0,0 +1,20 @@
+use axum::Router;
+
+use crate::state::ApplicationState;
+
+pub mod notebooks_routes;
+pub mod notes_routes;
+pub mod public_routes;
+pub mod sessions_routes;
+pub mod tags_routes;
+pub mod users_routes;
+
+pub fn routes(state: ApplicationState) -> Router {
+    Router::new()
+        .nest("/users", users_routes::routes(state.clone()))
+        .nest("/sessions", sessions_routes::routes(state.clone()))
+        .nest("/notes", notes_routes::routes(state.clone()))
+        .nest("/notebooks", notebooks_routes::routes(state.clone()))
+        .nest("/tags", tags_routes::routes(state.clone()))
+        .nest("/public", public_routes::routes(state))
+}
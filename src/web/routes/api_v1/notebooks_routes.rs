@@ -0,0 +1,113 @@
+use axum::Router;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::middleware::{from_fn, from_fn_with_state};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, patch};
+
+use crate::context::AuthTokenContext;
+use crate::error::Result;
+use crate::log::log_layer;
+use crate::model::notebooks::notebooks_models::{
+    NotebookCreate,
+    NotebookDelete,
+    NotebookEdit,
+};
+use crate::model::notes::notes_query::NotesQuery;
+use crate::state::ApplicationState;
+use crate::web::auth_middleware::{
+    require_auth_middleware,
+    token_context_resolver_middleware,
+};
+use crate::web::extract::{Json, Path, Query};
+use crate::web::routes::HANDLER;
+
+pub fn routes(state: ApplicationState) -> Router {
+    Router::new()
+        .route(
+            "/",
+            get(tree_of_notebooks_handler)
+                .post(create_notebook_handler),
+        )
+        .route(
+            "/:id",
+            patch(edit_notebook_handler)
+                .delete(delete_notebook_handler),
+        )
+        .route("/:id/notes", get(notes_of_notebook_handler))
+        .with_state(state.clone())
+        .layer(from_fn(require_auth_middleware))
+        .layer(from_fn_with_state(state, token_context_resolver_middleware))
+}
+
+async fn create_notebook_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Json(notebook): Json<NotebookCreate>,
+) -> Result<Response> {
+    log_layer(HANDLER, "create_notebook");
+
+    let notebook = state.database.notebooks
+        .create_notebook(&context?, notebook)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(notebook)).into_response())
+}
+
+async fn tree_of_notebooks_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+) -> Result<Response> {
+    log_layer(HANDLER, "tree_of_notebooks");
+
+    let tree = state.database.notebooks
+        .tree(&context?)
+        .await?;
+
+    Ok(Json(tree).into_response())
+}
+
+async fn edit_notebook_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(notebook_id): Path<u64>,
+    Json(notebook_edit): Json<NotebookEdit>,
+) -> Result<Response> {
+    log_layer(HANDLER, "edit_notebook");
+
+    let notebook = state.database.notebooks
+        .edit_notebook(&context?, notebook_id, notebook_edit)
+        .await?;
+
+    Ok(Json(notebook).into_response())
+}
+
+async fn delete_notebook_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(notebook_id): Path<u64>,
+    Query(notebook_delete): Query<NotebookDelete>,
+) -> Result<Response> {
+    log_layer(HANDLER, "delete_notebook");
+
+    let notebook = state.database.notebooks
+        .delete_notebook(&context?, notebook_id, notebook_delete.notes)
+        .await?;
+
+    Ok(Json(notebook).into_response())
+}
+
+async fn notes_of_notebook_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(notebook_id): Path<u64>,
+    Query(query): Query<NotesQuery>,
+) -> Result<Response> {
+    log_layer(HANDLER, "notes_of_notebook");
+
+    let page = state.database.notebooks
+        .page_of_notes(&context?, notebook_id, query)
+        .await?;
+
+    Ok(Json(page).into_response())
+}
@@ -0,0 +1,371 @@
+use axum::Router;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{from_fn, from_fn_with_state};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+
+use crate::context::AuthTokenContext;
+use crate::error::Result;
+use crate::log::log_layer;
+use crate::model::links::links_models::NoteLinkCreate;
+use crate::model::notes::notes_models::{NoteCreate, NoteEdit};
+use crate::model::notes::notes_query::{NotesQuery, NotesSearchQuery};
+use crate::model::revisions::revisions_models::RevisionsDiffQuery;
+use crate::model::shares::shares_models::NoteShareCreate;
+use crate::state::ApplicationState;
+use crate::web::auth_middleware::{
+    require_auth_middleware,
+    token_context_resolver_middleware,
+};
+use crate::web::etag::{etag, if_match_version};
+use crate::web::extract::{Json, Path, Query};
+use crate::web::routes::HANDLER;
+
+pub fn routes(state: ApplicationState) -> Router {
+    Router::new()
+        .route(
+            "/",
+            get(list_of_notes_handler)
+                .post(create_note_handler),
+        )
+        .route("/search", get(search_notes_handler))
+        .route("/shared", get(shared_notes_handler))
+        .route("/trash", get(trash_of_notes_handler))
+        .route("/trash/:id", delete(purge_note_handler))
+        .route("/trash/:id/restore", post(restore_note_handler))
+        .route(
+            "/:id",
+            get(get_note_handler)
+                .patch(edit_note_handler)
+                .delete(delete_note_handler),
+        )
+        .route("/:id/revisions", get(revisions_of_note_handler))
+        .route("/:id/revisions/diff", get(diff_of_note_handler))
+        .route("/:id/revisions/:revision_id", get(revision_of_note_handler))
+        .route(
+            "/:id/revisions/:revision_id/restore",
+            post(restore_revision_handler),
+        )
+        .route(
+            "/:id/shares",
+            get(shares_of_note_handler)
+                .post(share_note_handler),
+        )
+        .route("/:id/shares/events", get(share_events_of_note_handler))
+        .route("/:id/shares/:share_id", delete(revoke_share_handler))
+        .route(
+            "/:id/links",
+            get(links_of_note_handler)
+                .post(create_link_handler),
+        )
+        .route("/:id/links/:link_id", delete(revoke_link_handler))
+        .with_state(state.clone())
+        .layer(from_fn(require_auth_middleware))
+        .layer(from_fn_with_state(state, token_context_resolver_middleware))
+}
+
+async fn create_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Json(note): Json<NoteCreate>,
+) -> Result<Response> {
+    log_layer(HANDLER, "create_note");
+
+    let note = state.database.notes
+        .create_note(note, context?.user_id())
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        [(header::ETAG, etag(note.version))],
+        Json(note),
+    ).into_response())
+}
+
+async fn list_of_notes_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Query(query): Query<NotesQuery>,
+) -> Result<Response> {
+    log_layer(HANDLER, "list_of_notes");
+
+    let page = state.database.notes
+        .page_of_notes(&context?, query)
+        .await?;
+
+    Ok(Json(page).into_response())
+}
+
+async fn search_notes_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Query(query): Query<NotesSearchQuery>,
+) -> Result<Response> {
+    log_layer(HANDLER, "search_notes");
+
+    let results = state.database.notes
+        .search_notes(&context?, query)
+        .await?;
+
+    Ok(Json(results).into_response())
+}
+
+async fn get_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(note_id): Path<u64>,
+) -> Result<Response> {
+    log_layer(HANDLER, "get_note");
+
+    let note = state.database.notes
+        .get_note(note_id, &context?)
+        .await?;
+
+    Ok(([(header::ETAG, etag(note.version))], Json(note)).into_response())
+}
+
+async fn edit_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(note_id): Path<u64>,
+    headers: HeaderMap,
+    Json(mut note_edit): Json<NoteEdit>,
+) -> Result<Response> {
+    log_layer(HANDLER, "edit_note");
+
+    if let Some(version) = if_match_version(&headers)? {
+        note_edit.expected_version = Some(version);
+    }
+
+    let note = state.database.notes
+        .edit_note(note_id, note_edit, &context?)
+        .await?;
+
+    Ok(([(header::ETAG, etag(note.version))], Json(note)).into_response())
+}
+
+async fn delete_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(note_id): Path<u64>,
+) -> Result<Response> {
+    log_layer(HANDLER, "delete_note");
+
+    let note = state.database.notes
+        .delete_note(note_id, &context?)
+        .await?;
+
+    Ok(Json(note).into_response())
+}
+
+async fn trash_of_notes_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+) -> Result<Response> {
+    log_layer(HANDLER, "trash_of_notes");
+
+    let notes = state.database.notes
+        .trash_of_notes(&context?)
+        .await?;
+
+    Ok(Json(notes).into_response())
+}
+
+async fn restore_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(note_id): Path<u64>,
+) -> Result<Response> {
+    log_layer(HANDLER, "restore_note");
+
+    let note = state.database.notes
+        .restore_note(note_id, &context?)
+        .await?;
+
+    Ok(([(header::ETAG, etag(note.version))], Json(note)).into_response())
+}
+
+async fn purge_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(note_id): Path<u64>,
+) -> Result<Response> {
+    log_layer(HANDLER, "purge_note");
+
+    let note = state.database.notes
+        .purge_note(note_id, &context?)
+        .await?;
+
+    Ok(Json(note).into_response())
+}
+
+async fn revisions_of_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(note_id): Path<u64>,
+) -> Result<Response> {
+    log_layer(HANDLER, "revisions_of_note");
+
+    let revisions = state.database.notes
+        .revisions_of_note(note_id, &context?)
+        .await?;
+
+    Ok(Json(revisions).into_response())
+}
+
+async fn revision_of_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path((note_id, revision_id)): Path<(u64, u64)>,
+) -> Result<Response> {
+    log_layer(HANDLER, "revision_of_note");
+
+    let revision = state.database.notes
+        .revision_of_note(note_id, revision_id, &context?)
+        .await?;
+
+    Ok(Json(revision).into_response())
+}
+
+async fn diff_of_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(note_id): Path<u64>,
+    Query(query): Query<RevisionsDiffQuery>,
+) -> Result<Response> {
+    log_layer(HANDLER, "diff_of_note");
+
+    let diff = state.database.notes
+        .diff_of_note(note_id, query, &context?)
+        .await?;
+
+    Ok(Json(diff).into_response())
+}
+
+async fn restore_revision_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path((note_id, revision_id)): Path<(u64, u64)>,
+) -> Result<Response> {
+    log_layer(HANDLER, "restore_revision");
+
+    let note = state.database.notes
+        .restore_revision(note_id, revision_id, &context?)
+        .await?;
+
+    Ok(([(header::ETAG, etag(note.version))], Json(note)).into_response())
+}
+
+async fn shared_notes_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+) -> Result<Response> {
+    log_layer(HANDLER, "shared_notes");
+
+    let notes = state.database.notes
+        .shared_notes(&context?)
+        .await?;
+
+    Ok(Json(notes).into_response())
+}
+
+async fn shares_of_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(note_id): Path<u64>,
+) -> Result<Response> {
+    log_layer(HANDLER, "shares_of_note");
+
+    let shares = state.database.notes
+        .shares_of_note(note_id, &context?)
+        .await?;
+
+    Ok(Json(shares).into_response())
+}
+
+async fn share_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(note_id): Path<u64>,
+    Json(share_create): Json<NoteShareCreate>,
+) -> Result<Response> {
+    log_layer(HANDLER, "share_note");
+
+    let share = state.database.notes
+        .share_note(note_id, share_create, &context?)
+        .await?;
+
+    Ok(Json(share).into_response())
+}
+
+async fn revoke_share_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path((note_id, share_id)): Path<(u64, u64)>,
+) -> Result<Response> {
+    log_layer(HANDLER, "revoke_share");
+
+    let share = state.database.notes
+        .revoke_share(note_id, share_id, &context?)
+        .await?;
+
+    Ok(Json(share).into_response())
+}
+
+async fn share_events_of_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(note_id): Path<u64>,
+) -> Result<Response> {
+    log_layer(HANDLER, "share_events_of_note");
+
+    let events = state.database.notes
+        .share_events_of_note(note_id, &context?)
+        .await?;
+
+    Ok(Json(events).into_response())
+}
+
+async fn links_of_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(note_id): Path<u64>,
+) -> Result<Response> {
+    log_layer(HANDLER, "links_of_note");
+
+    let links = state.database.links
+        .links_of_note(note_id, &context?)
+        .await?;
+
+    Ok(Json(links).into_response())
+}
+
+async fn create_link_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(note_id): Path<u64>,
+    Json(link_create): Json<NoteLinkCreate>,
+) -> Result<Response> {
+    log_layer(HANDLER, "create_link");
+
+    let link = state.database.links
+        .create_link(note_id, link_create, &context?)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(link)).into_response())
+}
+
+async fn revoke_link_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path((note_id, link_id)): Path<(u64, u64)>,
+) -> Result<Response> {
+    log_layer(HANDLER, "revoke_link");
+
+    let link = state.database.links
+        .revoke_link(note_id, link_id, &context?)
+        .await?;
+
+    Ok(Json(link).into_response())
+}
@@ -0,0 +1,138 @@
+use axum::Router;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+
+use crate::error::{Error, NoteError, Result};
+use crate::log::log_layer;
+use crate::model::links::links_models::{NoteLinkUnlock, PublicNote};
+use crate::state::ApplicationState;
+use crate::web::extract::{Form, Json, Path};
+use crate::web::routes::HANDLER;
+
+/// Routes opened without an account, so they are kept
+/// out of the authentication middlewares.
+pub fn routes(state: ApplicationState) -> Router {
+    Router::new()
+        .route(
+            "/notes/:token",
+            get(public_note_handler)
+                .post(unlock_public_note_handler),
+        )
+        .with_state(state)
+}
+
+async fn public_note_handler(
+    State(state): State<ApplicationState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    log_layer(HANDLER, "public_note");
+
+    let note = state.database.links
+        .public_note(&token, None)
+        .await;
+
+    render(note, &headers)
+}
+
+/// Opens a password protected link, the password is sent as a form
+/// so that it is never a part of the URL.
+async fn unlock_public_note_handler(
+    State(state): State<ApplicationState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+    Form(unlock): Form<NoteLinkUnlock>,
+) -> Result<Response> {
+    log_layer(HANDLER, "unlock_public_note");
+
+    let note = state.database.links
+        .public_note(&token, Some(unlock.password))
+        .await;
+
+    render(note, &headers)
+}
+
+/// Renders HTML for browsers and JSON for everyone else. Browsers are
+/// shown a password form instead of the error of a protected link.
+fn render(note: Result<PublicNote>, headers: &HeaderMap) -> Result<Response> {
+    let wants_html = headers.get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"));
+
+    let mut response = match (note, wants_html) {
+        (Ok(note), false) => Json(note).into_response(),
+        (Ok(note), true) => Html(note_page(&note)).into_response(),
+        (Err(Error::Notes(NoteError::LinkPasswordRequired)), true) =>
+            (StatusCode::UNAUTHORIZED, Html(password_page(false))).into_response(),
+        (Err(Error::Notes(NoteError::LinkPasswordWrong)), true) =>
+            (StatusCode::FORBIDDEN, Html(password_page(true))).into_response(),
+        (Err(error), _) => return Err(error),
+    };
+
+    let headers = response.headers_mut();
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    headers.insert(header::REFERRER_POLICY, HeaderValue::from_static("no-referrer"));
+    headers.insert("x-robots-tag", HeaderValue::from_static("noindex"));
+
+    Ok(response)
+}
+
+fn note_page(note: &PublicNote) -> String {
+    page(
+        &note.title,
+        &format!(
+            "<h1>{}</h1>\n<p><small>Updated {}</small></p>\n\
+            <div style=\"white-space: pre-wrap\">{}</div>",
+            escape_html(&note.title),
+            note.updated_at.format("%Y-%m-%d %H:%M UTC"),
+            escape_html(&note.body),
+        ),
+    )
+}
+
+fn password_page(is_wrong: bool) -> String {
+    let message = if is_wrong {
+        "<p>Password is wrong, try again.</p>\n"
+    } else {
+        ""
+    };
+
+    page(
+        "Protected note",
+        &format!(
+            "<h1>Protected note</h1>\n{message}\
+            <form method=\"post\">\n\
+            <input type=\"password\" name=\"password\" autofocus required>\n\
+            <button type=\"submit\">Open</button>\n\
+            </form>"
+        ),
+    )
+}
+
+fn page(title: &str, content: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+        <meta name=\"robots\" content=\"noindex\">\n<title>{}</title>\n</head>\n\
+        <body>\n{content}\n</body>\n</html>\n",
+        escape_html(title),
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,136 @@
+use axum::Router;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::middleware::{from_fn, from_fn_with_state};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum_extra::extract::CookieJar;
+use tap::Tap;
+
+use crate::context::AuthTokenContext;
+use crate::error::Result;
+use crate::log::log_layer;
+use crate::model::users::users_models::{UserLogin, UserProfile};
+use crate::state::ApplicationState;
+use crate::web::auth_middleware::{
+    logout,
+    refresh,
+    require_auth_middleware,
+    set_auth_token_middleware,
+    token_context_resolver_middleware,
+};
+use crate::web::extract::{Json, Path};
+use crate::web::routes::HANDLER;
+
+pub fn routes(state: ApplicationState) -> Router {
+    Router::new()
+        .merge(set_up_token_routes(state.clone()))
+        .merge(authenticate_routes(state))
+}
+
+fn set_up_token_routes(state: ApplicationState) -> Router {
+    Router::new()
+        .route("/", post(create_session_handler))
+        .with_state(state.clone())
+        .layer(from_fn_with_state(state.clone(), set_auth_token_middleware))
+        .merge(token_cookie_routes(state))
+}
+
+/// Refreshing and logging out work with an expired access token,
+/// so they are kept out of the authentication middlewares.
+fn token_cookie_routes(state: ApplicationState) -> Router {
+    Router::new()
+        .route("/refresh", post(refresh_session_handler))
+        .route("/current", delete(delete_current_session_handler))
+        .with_state(state)
+}
+
+fn authenticate_routes(state: ApplicationState) -> Router {
+    Router::new()
+        .route("/", get(list_of_sessions_handler))
+        .route("/others", delete(revoke_other_sessions_handler))
+        .route("/:id", delete(revoke_session_handler))
+        .with_state(state.clone())
+        .layer(from_fn(require_auth_middleware))
+        .layer(from_fn_with_state(state, token_context_resolver_middleware))
+}
+
+async fn create_session_handler(
+    State(state): State<ApplicationState>,
+    Json(user_login): Json<UserLogin>,
+) -> Result<Response> {
+    log_layer(HANDLER, "create_session");
+
+    let user = state.database.users
+        .login(user_login)
+        .await?;
+
+    let context = AuthTokenContext::new(user.id, user.role);
+
+    let response = (StatusCode::CREATED, Json(UserProfile::from(user)))
+        .into_response()
+        .tap_mut(|response| {
+            response.extensions_mut()
+                .insert(context);
+        });
+
+    Ok(response)
+}
+
+async fn refresh_session_handler(
+    State(state): State<ApplicationState>,
+    cookies: CookieJar,
+) -> Result<Response> {
+    log_layer(HANDLER, "refresh_session");
+
+    refresh(&state, &cookies).await
+}
+
+async fn delete_current_session_handler(
+    State(state): State<ApplicationState>,
+    cookies: CookieJar,
+) -> Result<Response> {
+    log_layer(HANDLER, "delete_current_session");
+
+    logout(&state, &cookies).await
+}
+
+async fn list_of_sessions_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+) -> Result<Response> {
+    log_layer(HANDLER, "list_of_sessions");
+
+    let sessions = state.database.sessions
+        .sessions_of_user(&context?)
+        .await?;
+
+    Ok(Json(sessions).into_response())
+}
+
+async fn revoke_session_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(session_id): Path<u32>,
+) -> Result<Response> {
+    log_layer(HANDLER, "revoke_session");
+
+    let session = state.database.sessions
+        .revoke_session(&context?, session_id)
+        .await?;
+
+    Ok(Json(session).into_response())
+}
+
+async fn revoke_other_sessions_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+) -> Result<Response> {
+    log_layer(HANDLER, "revoke_other_sessions");
+
+    let sessions = state.database.sessions
+        .revoke_other_sessions(&context?)
+        .await?;
+
+    Ok(Json(sessions).into_response())
+}
@@ -0,0 +1,70 @@
+use axum::Router;
+use axum::extract::State;
+use axum::middleware::{from_fn, from_fn_with_state};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, patch, post};
+
+use crate::context::AuthTokenContext;
+use crate::error::Result;
+use crate::log::log_layer;
+use crate::model::notes::notes_tags::{TagMerge, TagRename};
+use crate::state::ApplicationState;
+use crate::web::auth_middleware::{
+    require_auth_middleware,
+    token_context_resolver_middleware,
+};
+use crate::web::extract::{Json, Path};
+use crate::web::routes::HANDLER;
+
+pub fn routes(state: ApplicationState) -> Router {
+    Router::new()
+        .route("/", get(list_of_tags_handler))
+        .route("/:name", patch(rename_tag_handler))
+        .route("/:name/merge", post(merge_tag_handler))
+        .with_state(state.clone())
+        .layer(from_fn(require_auth_middleware))
+        .layer(from_fn_with_state(state, token_context_resolver_middleware))
+}
+
+async fn list_of_tags_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+) -> Result<Response> {
+    log_layer(HANDLER, "list_of_tags");
+
+    let tags = state.database.notes
+        .tags(&context?)
+        .await?;
+
+    Ok(Json(tags).into_response())
+}
+
+async fn rename_tag_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(name): Path<String>,
+    Json(tag_rename): Json<TagRename>,
+) -> Result<Response> {
+    log_layer(HANDLER, "rename_tag");
+
+    let tag = state.database.notes
+        .rename_tag(&context?, &name, tag_rename)
+        .await?;
+
+    Ok(Json(tag).into_response())
+}
+
+async fn merge_tag_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(name): Path<String>,
+    Json(tag_merge): Json<TagMerge>,
+) -> Result<Response> {
+    log_layer(HANDLER, "merge_tag");
+
+    let tag = state.database.notes
+        .merge_tag(&context?, &name, tag_merge)
+        .await?;
+
+    Ok(Json(tag).into_response())
+}
@@ -0,0 +1,194 @@
+use axum::Router;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::middleware::{from_fn, from_fn_with_state};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, patch, post};
+use tap::Tap;
+
+use crate::context::AuthTokenContext;
+use crate::error::Result;
+use crate::log::log_layer;
+use crate::model::users::users_models::{
+    UserCreate,
+    UserDelete,
+    UserEdit,
+    UserProfile,
+};
+use crate::state::ApplicationState;
+use crate::web::auth_middleware::{
+    require_auth_middleware,
+    set_auth_token_middleware,
+    token_context_resolver_middleware,
+};
+use crate::web::extract::{Json, Path};
+use crate::web::routes::{require_admin, HANDLER};
+
+pub fn routes(state: ApplicationState) -> Router {
+    Router::new()
+        .merge(set_up_token_routes(state.clone()))
+        .merge(authenticate_routes(state))
+}
+
+fn set_up_token_routes(state: ApplicationState) -> Router {
+    Router::new()
+        .route("/", post(create_user_handler))
+        .with_state(state.clone())
+        .layer(from_fn_with_state(state, set_auth_token_middleware))
+}
+
+fn authenticate_routes(state: ApplicationState) -> Router {
+    Router::new()
+        .route(
+            "/me",
+            get(get_me_handler)
+                .patch(edit_me_handler)
+                .delete(delete_me_handler),
+        )
+        .route(
+            "/:id",
+            patch(edit_user_handler)
+                .delete(delete_user_handler),
+        )
+        .with_state(state.clone())
+        .layer(from_fn(require_auth_middleware))
+        .layer(from_fn_with_state(state, token_context_resolver_middleware))
+}
+
+async fn create_user_handler(
+    State(state): State<ApplicationState>,
+    Json(user_create): Json<UserCreate>,
+) -> Result<Response> {
+    log_layer(HANDLER, "create_user");
+
+    let user = state.database.users
+        .create_user(user_create)
+        .await?;
+
+    let context = AuthTokenContext::new(user.id, user.role);
+
+    let response = (StatusCode::CREATED, Json(UserProfile::from(user)))
+        .into_response()
+        .tap_mut(|response| {
+            response.extensions_mut()
+                .insert(context);
+        });
+
+    Ok(response)
+}
+
+async fn get_me_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+) -> Result<impl IntoResponse> {
+    log_layer(HANDLER, "get_me");
+
+    let user = state.database.users
+        .user(context?.user_id())
+        .await?;
+
+    Ok(Json(UserProfile::from(user)))
+}
+
+async fn edit_me_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Json(user_edit): Json<UserEdit>,
+) -> Result<impl IntoResponse> {
+    log_layer(HANDLER, "edit_me");
+
+    let context = context?;
+    let changes_password = user_edit.new_password.is_some();
+
+    let user = state.database.users
+        .edit_user(context.user_id(), user_edit)
+        .await?;
+
+    if changes_password {
+        state.database.sessions
+            .delete_other_sessions_of_user(user.id, context.session_id())
+            .await?;
+    }
+
+    Ok(Json(UserProfile::from(user)))
+}
+
+async fn delete_me_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Json(user_delete): Json<UserDelete>,
+) -> Result<impl IntoResponse> {
+    log_layer(HANDLER, "delete_me");
+
+    let user_id = context?.user_id();
+
+    state.database.users
+        .user_to_delete(user_id, user_delete.password)
+        .await?;
+
+    state.database.sessions
+        .delete_sessions_of_user(user_id)
+        .await?;
+
+    state.database.notebooks
+        .delete_content_of_user(user_id)
+        .await?;
+
+    let user = state.database.users
+        .delete_user(user_id)
+        .await?;
+
+    Ok(Json(UserProfile::from(user)))
+}
+
+async fn edit_user_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(user_id): Path<u32>,
+    Json(user_edit): Json<UserEdit>,
+) -> Result<impl IntoResponse> {
+    log_layer(HANDLER, "edit_user");
+
+    let context = require_admin(context?)?;
+    let changes_password = user_edit.new_password.is_some();
+
+    let user = state.database.users
+        .edit_user_as_admin(user_id, user_edit)
+        .await?;
+
+    if changes_password {
+        state.database.sessions
+            .delete_other_sessions_of_user(user.id, context.session_id())
+            .await?;
+    }
+
+    Ok(Json(UserProfile::from(user)))
+}
+
+async fn delete_user_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(user_id): Path<u32>,
+) -> Result<impl IntoResponse> {
+    log_layer(HANDLER, "delete_user");
+
+    require_admin(context?)?;
+
+    state.database.users
+        .user_to_delete_as_admin(user_id)
+        .await?;
+
+    state.database.sessions
+        .delete_sessions_of_user(user_id)
+        .await?;
+
+    state.database.notebooks
+        .delete_content_of_user(user_id)
+        .await?;
+
+    let user = state.database.users
+        .delete_user(user_id)
+        .await?;
+
+    Ok(Json(UserProfile::from(user)))
+}
@@ -1,4 +1,16 @@
+use crate::context::AuthTokenContext;
+use crate::error::{Error, Result, UserError};
+
+pub mod api_v1;
 pub mod users_routes;
 pub mod notes_routes;
 
-const HANDLER: &str = "HANDLER";
\ No newline at end of file
+const HANDLER: &str = "HANDLER";
+
+fn require_admin(context: AuthTokenContext) -> Result<AuthTokenContext> {
+    if context.is_admin() {
+        Ok(context)
+    } else {
+        Err(Error::User(UserError::AdminRightsRequired))
+    }
+}
\ No newline at end of file
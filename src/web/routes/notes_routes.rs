@@ -1,4 +1,4 @@
-use axum::{Json, Router};
+use axum::Router;
 use axum::extract::State;
 use axum::middleware::{from_fn, from_fn_with_state};
 use axum::response::{IntoResponse, Response};
@@ -7,18 +7,20 @@ use axum::routing::{delete, get, post};
 use crate::context::AuthTokenContext;
 use crate::error::Result;
 use crate::log::log_layer;
-use crate::model::notes::notes_models::{NoteCreate, NoteEdit};
+use crate::model::notes::notes_models::{NoteCreate, NoteEditById};
 use crate::state::ApplicationState;
 use crate::web::auth_middleware::{
     require_auth_middleware, 
     token_context_resolver_middleware
 };
+use crate::web::extract::{Json, Path};
 use crate::web::routes::HANDLER;
 
 pub fn routes(state: ApplicationState) -> Router {
     Router::new()
         .route("/create", post(create_note_handler))
         .route("/list", get(list_of_notes_handler))
+        .route("/:id", get(get_note_handler))
         .route("/edit", post(edit_note_handler))
         .route("/delete", delete(delete_note_handler))
         .with_state(state.clone())
@@ -42,16 +44,32 @@ async fn create_note_handler(
     Ok(Json(note).into_response())
 }
 
+async fn get_note_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(note_id): Path<u64>,
+) -> Result<Response> {
+    log_layer(HANDLER, "get_note");
+
+    let context = context?;
+
+    let note = state.database.notes
+        .get_note(note_id, &context)
+        .await?;
+
+    Ok(Json(note).into_response())
+}
+
 async fn list_of_notes_handler(
     context: Result<AuthTokenContext>,
     State(state): State<ApplicationState>
 ) -> Result<Response> {
     log_layer(HANDLER, "list_of_notes");
     
-    let user_id = context?.user_id();
+    let context = context?;
     
     let notes = state.database.notes
-        .list_of_notes(user_id)
+        .list_of_notes(&context)
         .await?;
     
     Ok(Json(notes).into_response())
@@ -60,14 +78,14 @@ async fn list_of_notes_handler(
 async fn edit_note_handler(
     context: Result<AuthTokenContext>,
     State(state): State<ApplicationState>,
-    Json(note): Json<NoteEdit>
+    Json(note): Json<NoteEditById>
 ) -> Result<Response> {
     log_layer(HANDLER, "edit_note");
     
-    let user_id = context?.user_id();
+    let context = context?;
     
     let note = state.database.notes
-        .edit_note(note, user_id)
+        .edit_note(note.id, note.note_edit, &context)
         .await?;
     
     Ok(Json(note).into_response())
@@ -80,10 +98,10 @@ async fn delete_note_handler(
 ) -> Result<Response> {
     log_layer(HANDLER, "delete_note");
     
-    let user_id = context?.user_id();
+    let context = context?;
     
     let note = state.database.notes
-        .delete_note(note_id, user_id)
+        .delete_note(note_id, &context)
         .await?;
     
     Ok(Json(note).into_response())
@@ -1,17 +1,30 @@
-use axum::{Json, Router};
+use axum::Router;
 use axum::extract::State;
-use axum::middleware::{from_fn, from_fn_with_state, map_response_with_state};
+use axum::middleware::{from_fn, from_fn_with_state};
 use axum::response::{IntoResponse, Response};
 use axum::routing::{delete, post};
+use axum_extra::extract::CookieJar;
 use tap::Tap;
 
 use crate::context::AuthTokenContext;
 use crate::error::Result;
 use crate::log::log_layer;
-use crate::model::users::users_models::{UserCreate, UserEdit, UserLogin};
+use crate::model::users::users_models::{
+    UserCreate,
+    UserDelete,
+    UserEdit,
+    UserLogin,
+    UserProfile,
+};
 use crate::state::ApplicationState;
-use crate::web::auth_middleware::{require_auth_middleware, set_auth_token_middleware, token_context_resolver_middleware};
-use crate::web::routes::HANDLER;
+use crate::web::auth_middleware::{
+    logout,
+    require_auth_middleware,
+    set_legacy_auth_token_middleware,
+    token_context_resolver_middleware,
+};
+use crate::web::extract::{Json, Path};
+use crate::web::routes::{require_admin, HANDLER};
 
 pub fn routes(state: ApplicationState) -> Router {
     Router::new()
@@ -24,13 +37,22 @@ fn set_up_token_routes(state: ApplicationState) -> Router {
         .route("/register", post(register_handler))
         .route("/login", post(login_handler))
         .with_state(state.clone())
-        .layer(map_response_with_state(state, set_auth_token_middleware))
+        .layer(from_fn_with_state(state.clone(), set_legacy_auth_token_middleware))
+        .merge(logout_routes(state))
+}
+
+fn logout_routes(state: ApplicationState) -> Router {
+    Router::new()
+        .route("/logout", post(logout_handler))
+        .with_state(state)
 }
 
 fn authenticate_routes(state: ApplicationState) -> Router {
     Router::new()
         .route("/edit", post(edit_handler))
         .route("/delete", delete(delete_handler))
+        .route("/:id/edit", post(admin_edit_handler))
+        .route("/:id/delete", delete(admin_delete_handler))
         .with_state(state.clone())
         .layer(from_fn(require_auth_middleware))
         .layer(from_fn_with_state(state, token_context_resolver_middleware))
@@ -46,9 +68,9 @@ async fn register_handler(
         .create_user(user_create)
         .await?;
 
-    let context = AuthTokenContext::new(user.id);
+    let context = AuthTokenContext::new(user.id, user.role);
 
-    let response = Json(user).into_response()
+    let response = Json(UserProfile::from(user)).into_response()
         .tap_mut(|response| {
             response.extensions_mut()
                 .insert(context);
@@ -57,6 +79,15 @@ async fn register_handler(
     Ok(response)
 }
 
+async fn logout_handler(
+    State(state): State<ApplicationState>,
+    cookies: CookieJar,
+) -> Result<Response> {
+    log_layer(HANDLER, "logout");
+
+    logout(&state, &cookies).await
+}
+
 async fn login_handler(
     State(state): State<ApplicationState>,
     Json(user_login): Json<UserLogin>,
@@ -67,9 +98,9 @@ async fn login_handler(
         .login(user_login)
         .await?;
 
-    let context = AuthTokenContext::new(user.id);
+    let context = AuthTokenContext::new(user.id, user.role);
 
-    let response = Json(user).into_response()
+    let response = Json(UserProfile::from(user)).into_response()
         .tap_mut(|response| {
             response.extensions_mut()
                 .insert(context);
@@ -79,31 +110,104 @@ async fn login_handler(
 }
 
 async fn edit_handler(
+    context: Result<AuthTokenContext>,
     State(state): State<ApplicationState>,
     Json(user_edit): Json<UserEdit>,
 ) -> Result<impl IntoResponse> {
     log_layer(HANDLER, "edit");
-    
+
+    let context = context?;
+    let changes_password = user_edit.new_password.is_some();
+
     let user = state.database.users
-        .edit_user(user_edit)
+        .edit_user(context.user_id(), user_edit)
         .await?;
 
-    Ok(Json(user))
+    if changes_password {
+        state.database.sessions
+            .delete_other_sessions_of_user(user.id, context.session_id())
+            .await?;
+    }
+
+    Ok(Json(UserProfile::from(user)))
 }
 
 async fn delete_handler(
+    context: Result<AuthTokenContext>,
     State(state): State<ApplicationState>,
-    Json(user_id): Json<u32>,
+    Json(user_delete): Json<UserDelete>,
 ) -> Result<impl IntoResponse> {
     log_layer(HANDLER, "delete");
-    
+
+    let user_id = context?.user_id();
+
+    state.database.users
+        .user_to_delete(user_id, user_delete.password)
+        .await?;
+
+    state.database.sessions
+        .delete_sessions_of_user(user_id)
+        .await?;
+
+    state.database.notebooks
+        .delete_content_of_user(user_id)
+        .await?;
+
     let user = state.database.users
         .delete_user(user_id)
         .await?;
-    
+
+    Ok(Json(UserProfile::from(user)))
+}
+
+async fn admin_edit_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(user_id): Path<u32>,
+    Json(user_edit): Json<UserEdit>,
+) -> Result<impl IntoResponse> {
+    log_layer(HANDLER, "admin_edit");
+
+    let context = require_admin(context?)?;
+    let changes_password = user_edit.new_password.is_some();
+
+    let user = state.database.users
+        .edit_user_as_admin(user_id, user_edit)
+        .await?;
+
+    if changes_password {
+        state.database.sessions
+            .delete_other_sessions_of_user(user.id, context.session_id())
+            .await?;
+    }
+
+    Ok(Json(UserProfile::from(user)))
+}
+
+async fn admin_delete_handler(
+    context: Result<AuthTokenContext>,
+    State(state): State<ApplicationState>,
+    Path(user_id): Path<u32>,
+) -> Result<impl IntoResponse> {
+    log_layer(HANDLER, "admin_delete");
+
+    require_admin(context?)?;
+
+    state.database.users
+        .user_to_delete_as_admin(user_id)
+        .await?;
+
     state.database.sessions
-        .delete_session(user_id)
+        .delete_sessions_of_user(user_id)
+        .await?;
+
+    state.database.notebooks
+        .delete_content_of_user(user_id)
+        .await?;
+
+    let user = state.database.users
+        .delete_user(user_id)
         .await?;
 
-    Ok(Json(user))
+    Ok(Json(UserProfile::from(user)))
 }
\ No newline at end of file